@@ -0,0 +1,275 @@
+//! Previews messages the way a `LedPrinter` shows them on a matrix, in a terminal or an SDL window.
+//!
+//! Every line read from stdin is shown as the new message, lines starting with `/` change settings instead:
+//! `/color RRGGBB`, `/background RRGGBB`, `/speed MS`, `/mode MODE`, `/pause`, `/resume`, `/clear` and `/quit`.
+
+use std::error::Error;
+use std::io::{stdin, stdout, BufRead};
+use std::process::exit;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::{Arc, RwLock};
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::Size;
+use embedded_graphics::text::Alignment;
+use eg_simple_status_messaging::{LedPrinter, ScrollMode, TerminalTarget};
+
+const USAGE: &str = "usage: led-preview [--width N] [--height N] [--speed MS] [--mode MODE] [--color RRGGBB] [--background RRGGBB] [--backend terminal|sdl] [TEXT]
+
+MODE is bounce, marquee[:GAP], rtl, ltr or static[:left|center|right].
+Lines read from stdin replace the message, /help lists the commands.";
+
+const HELP: &str = "/color RRGGBB, /background RRGGBB, /speed MS, /mode MODE, /pause, /resume, /clear, /quit";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Backend {
+    Terminal,
+    Sdl,
+}
+
+#[derive(Debug)]
+struct Options {
+    size: Size,
+    speed: u16,
+    mode: ScrollMode,
+    color: Rgb888,
+    background: Rgb888,
+    backend: Backend,
+    text: String,
+}
+
+fn parse_color(value: &str) -> Result<Rgb888, String> {
+    let value = value.trim_start_matches('#');
+    let rgb = u32::from_str_radix(value, 16).ok().filter(|_| value.len() == 6).ok_or_else(|| format!("not a RRGGBB color: {value}"))?;
+    Ok(Rgb888::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+fn parse_mode(value: &str) -> Result<ScrollMode, String> {
+    let (name, argument) = value.split_once(':').map_or((value, None), |(name, argument)| (name, Some(argument)));
+    match (name, argument) {
+        ("bounce", None) => Ok(ScrollMode::Bounce),
+        ("marquee", gap) => Ok(ScrollMode::Marquee { gap: parse_number(gap.unwrap_or("4"))? }),
+        ("rtl", None) => Ok(ScrollMode::RightToLeft),
+        ("ltr", None) => Ok(ScrollMode::LeftToRight),
+        ("static", None | Some("left")) => Ok(ScrollMode::Static(Alignment::Left)),
+        ("static", Some("center")) => Ok(ScrollMode::Static(Alignment::Center)),
+        ("static", Some("right")) => Ok(ScrollMode::Static(Alignment::Right)),
+        _ => Err(format!("unknown mode: {value}"))
+    }
+}
+
+fn parse_number<T>(value: &str) -> Result<T, String> where T: std::str::FromStr {
+    value.parse().map_err(|_| format!("not a number: {value}"))
+}
+
+fn parse_args(mut args: impl Iterator<Item=String>) -> Result<Options, String> {
+    let mut options = Options {
+        size: Size::new(32, 8),
+        speed: 75,
+        mode: ScrollMode::default(),
+        color: Rgb888::new(255, 255, 255),
+        background: Rgb888::new(0, 0, 0),
+        backend: Backend::Terminal,
+        text: "Hello, World!".to_string(),
+    };
+    let mut words = Vec::new();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            words.push(arg);
+            continue;
+        }
+        if arg == "--help" {
+            return Err(USAGE.to_string());
+        }
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+        match arg.as_str() {
+            "--width" => options.size.width = parse_number(&value)?,
+            "--height" => options.size.height = parse_number(&value)?,
+            "--speed" => options.speed = parse_number(&value)?,
+            "--mode" => options.mode = parse_mode(&value)?,
+            "--color" => options.color = parse_color(&value)?,
+            "--background" => options.background = parse_color(&value)?,
+            "--backend" => options.backend = match value.as_str() {
+                "terminal" => Backend::Terminal,
+                "sdl" => Backend::Sdl,
+                _ => return Err(format!("unknown backend: {value}"))
+            },
+            _ => return Err(format!("unknown option: {arg}\n\n{USAGE}"))
+        }
+    }
+    if !words.is_empty() {
+        options.text = words.join(" ");
+    }
+    Ok(options)
+}
+
+/// Reads stdin on its own thread so the preview keeps animating while waiting for input.
+fn read_lines() -> Receiver<String> {
+    let (lines, receiver) = channel();
+    spawn(move || {
+        for line in stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Applies one line of input, returns whether to keep going.
+fn handle_line<E, Target>(printer: &mut LedPrinter<Rgb888, E, Target>, options: &mut Options, line: &str) -> Result<bool, String>
+    where Target: DrawTarget<Color=Rgb888, Error=E> + Send + Sync + 'static, E: Error + Send + 'static {
+    let Some(command) = line.strip_prefix('/') else {
+        options.text = line.to_string();
+        printer.display(&options.text, options.color, options.background).map_err(|error| error.to_string())?;
+        return Ok(true);
+    };
+    let (name, value) = command.split_once(' ').map_or((command, ""), |(name, value)| (name, value.trim()));
+    match name {
+        "color" => options.color = parse_color(value)?,
+        "background" => options.background = parse_color(value)?,
+        "speed" => {
+            options.speed = parse_number(value)?;
+            printer.set_scroll_speed(options.speed);
+            return Ok(true);
+        }
+        "mode" => {
+            options.mode = parse_mode(value)?;
+            printer.set_scroll_mode(options.mode);
+            return Ok(true);
+        }
+        "pause" => return printer.pause().map(|_| true).map_err(|error| error.to_string()),
+        "resume" => return printer.resume().map(|_| true).map_err(|error| error.to_string()),
+        "clear" => return printer.clear(options.background).map(|_| true).map_err(|error| error.to_string()),
+        "quit" => return Ok(false),
+        "help" => {
+            eprintln!("{HELP}");
+            return Ok(true);
+        }
+        _ => return Err(format!("unknown command: /{name}, try /help"))
+    }
+    // Color changes apply to the message on screen.
+    printer.display(&options.text, options.color, options.background).map_err(|error| error.to_string())?;
+    Ok(true)
+}
+
+/// Runs the preview until stdin closes or `render` asks to stop.
+///
+/// `render` is called about every 16 ms, and told whether a line was read since its last call.
+fn run<E, Target>(screen: Arc<RwLock<Target>>, mut options: Options, mut render: impl FnMut(&RwLock<Target>, bool) -> bool) -> Result<(), String>
+    where Target: DrawTarget<Color=Rgb888, Error=E> + Send + Sync + 'static, E: Error + Send + 'static {
+    let mut printer = LedPrinter::new(Arc::clone(&screen), options.speed).map_err(|error| error.to_string())?;
+    printer.set_scroll_mode(options.mode);
+    printer.display(&options.text, options.color, options.background).map_err(|error| error.to_string())?;
+
+    let lines = read_lines();
+    loop {
+        let read_line = match lines.try_recv() {
+            Ok(line) => {
+                match handle_line(&mut printer, &mut options, &line) {
+                    Ok(true) => {}
+                    Ok(false) => return Ok(()),
+                    Err(error) => eprintln!("{error}")
+                }
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => return Ok(())
+        };
+        if let Some(error) = printer.take_error() {
+            return Err(error.to_string());
+        }
+        if !render(&screen, read_line) {
+            return Ok(());
+        }
+        sleep(Duration::from_millis(16));
+    }
+}
+
+fn run_terminal(options: Options) -> Result<(), String> {
+    let screen = Arc::new(RwLock::new(TerminalTarget::new(options.size, options.background, stdout())));
+    run(screen, options, |screen, read_line| {
+        let mut screen = screen.write().unwrap();
+        if read_line {
+            // Whatever was typed moved the cursor, so start below it rather than drawing over it.
+            screen.detach();
+        }
+        screen.flush().is_ok()
+    })
+}
+
+#[cfg(feature = "simulator")]
+fn run_sdl(options: Options) -> Result<(), String> {
+    use embedded_graphics_simulator::{OutputSettingsBuilder, SimulatorDisplay, SimulatorEvent, Window};
+
+    let screen = Arc::new(RwLock::new(SimulatorDisplay::<Rgb888>::new(options.size)));
+    let mut window = Window::new("led-preview", &OutputSettingsBuilder::new().scale(20).build());
+    run(screen, options, |screen, _| {
+        window.update(&screen.read().unwrap());
+        !window.events().any(|event| matches!(event, SimulatorEvent::Quit))
+    })
+}
+
+#[cfg(not(feature = "simulator"))]
+fn run_sdl(_options: Options) -> Result<(), String> {
+    Err("built without the SDL backend, enable the simulator feature".to_string())
+}
+
+fn main() {
+    let result = parse_args(std::env::args().skip(1)).and_then(|options| match options.backend {
+        Backend::Terminal => run_terminal(options),
+        Backend::Sdl => run_sdl(options),
+    });
+    if let Err(error) = result {
+        eprintln!("{error}");
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Options, String> {
+        parse_args(line.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn modes_take_optional_arguments() {
+        assert_eq!(parse_mode("marquee:3"), Ok(ScrollMode::Marquee { gap: 3 }));
+        assert_eq!(parse_mode("marquee"), Ok(ScrollMode::Marquee { gap: 4 }));
+        assert_eq!(parse_mode("static:center"), Ok(ScrollMode::Static(Alignment::Center)));
+        assert_eq!(parse_mode("static"), Ok(ScrollMode::Static(Alignment::Left)));
+        assert!(parse_mode("bounce:2").is_err());
+        assert!(parse_mode("marquee:wide").is_err());
+    }
+
+    #[test]
+    fn colors_are_rrggbb() {
+        assert_eq!(parse_color("ff8000"), Ok(Rgb888::new(255, 128, 0)));
+        assert_eq!(parse_color("#0000ff"), Ok(Rgb888::new(0, 0, 255)));
+        assert!(parse_color("fff").is_err());
+        assert!(parse_color("orange").is_err());
+    }
+
+    #[test]
+    fn arguments_fill_in_the_options() {
+        let options = parse("--width 16 --mode static:right --color 00ff00 Build failed").unwrap();
+        assert_eq!(options.size, Size::new(16, 8));
+        assert_eq!(options.mode, ScrollMode::Static(Alignment::Right));
+        assert_eq!(options.color, Rgb888::new(0, 255, 0));
+        assert_eq!(options.backend, Backend::Terminal);
+        assert_eq!(options.text, "Build failed");
+        assert_eq!(parse("").unwrap().text, "Hello, World!");
+
+        assert_eq!(parse("--width").unwrap_err(), "--width needs a value");
+        assert!(parse("--width wide").is_err());
+        assert!(parse("--background red").is_err());
+        assert!(parse("--backend vga").is_err());
+        assert!(parse("--depth 3").unwrap_err().starts_with("unknown option: --depth"));
+        assert_eq!(parse("--help").unwrap_err(), USAGE);
+    }
+}
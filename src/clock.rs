@@ -0,0 +1,107 @@
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of time for the display task.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Wall clock time, for schedules that go by the time of day.
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    /// How long the display task may sleep waiting for `deadline`, `None` to sleep until the next command instead.
+    fn wait_time(&self, deadline: Instant) -> Option<Duration>;
+}
+
+/// The system's monotonic clock, used unless another clock is given.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wait_time(&self, deadline: Instant) -> Option<Duration> {
+        Some(deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+/// A clock that only moves when told to, for stepping frames deterministically.
+///
+/// The display task never wakes up by itself on a manual clock, call [`crate::LedPrinter::sync`] after
+/// moving the clock to have it catch up. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<(Instant, SystemTime)>>,
+}
+
+impl ManualClock {
+    /// A clock standing still at the current time.
+    pub fn new() -> Self {
+        ManualClock::starting_at(Instant::now(), SystemTime::now())
+    }
+
+    /// A clock standing still at `now`, which is `system_time` on the wall clock.
+    pub fn starting_at(now: Instant, system_time: SystemTime) -> Self {
+        ManualClock { now: Arc::new(Mutex::new((now, system_time))) }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut current = self.lock();
+        current.0 += duration;
+        current.1 += duration;
+    }
+
+    /// Moves the clock to `now`, the clock never goes backwards.
+    pub fn set(&self, now: Instant) {
+        let ahead = now.saturating_duration_since(self.lock().0);
+        self.advance(ahead);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, (Instant, SystemTime)> {
+        self.now.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.lock().0
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.lock().1
+    }
+
+    fn wait_time(&self, _deadline: Instant) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock_only_moves_forward() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let shared = clock.clone();
+        shared.advance(Duration::from_millis(40));
+        assert_eq!(clock.now(), start + Duration::from_millis(40));
+        clock.set(start);
+        assert_eq!(clock.now(), start + Duration::from_millis(40));
+        assert_eq!(clock.wait_time(start), None);
+        let wall = clock.system_time();
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(clock.system_time(), wall + Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,193 @@
+use std::time::Duration;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Dimensions, Point};
+use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+
+/// Turns the RGB colors of an effect into the color type of the target.
+pub(crate) type FromRgb<C> = fn(Rgb888) -> C;
+
+/// Colors text by position or time instead of with a single color, see [`crate::Message::with_color_effect`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorEffect {
+    /// Blend from `from` at the left edge of the text to `to` at its right edge.
+    Gradient { from: Rgb888, to: Rgb888 },
+    /// Hues across the text, going once around the color wheel every `spread` pixels and shifting by a full turn every `cycle`.
+    Rainbow { spread: u32, cycle: Duration },
+    /// Give each character the next color of `colors`, moving the colors on by one character every `step` if set.
+    CharacterCycle { colors: Vec<Rgb888>, step: Option<Duration> },
+}
+
+impl ColorEffect {
+    /// Whether the colors change over time, so static text has to be redrawn.
+    pub(crate) fn is_animated(&self) -> bool {
+        match self {
+            ColorEffect::Gradient { .. } => false,
+            ColorEffect::Rainbow { cycle, .. } => !cycle.is_zero(),
+            ColorEffect::CharacterCycle { step, .. } => step.is_some_and(|step| !step.is_zero()),
+        }
+    }
+
+    /// The color of a pixel `x` pixels into text `width` wide, in the `character`th character, `elapsed` into the animation.
+    pub(crate) fn color_at(&self, x: i32, width: i32, character: usize, elapsed: Duration) -> Rgb888 {
+        match self {
+            ColorEffect::Gradient { from, to } => {
+                let t = x.clamp(0, width) as f32 / (width - 1).max(1) as f32;
+                let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t.min(1.0)).round() as u8;
+                Rgb888::new(mix(from.r(), to.r()), mix(from.g(), to.g()), mix(from.b(), to.b()))
+            }
+            ColorEffect::Rainbow { spread, cycle } => {
+                let position = x as f32 / (*spread).max(1) as f32;
+                let time = if cycle.is_zero() { 0.0 } else { elapsed.as_secs_f32() / cycle.as_secs_f32() };
+                hue(position - time)
+            }
+            ColorEffect::CharacterCycle { colors, step } => {
+                if colors.is_empty() {
+                    return Rgb888::WHITE;
+                }
+                let shift = match step {
+                    Some(step) if !step.is_zero() => (elapsed.as_millis() / step.as_millis().max(1)) as usize,
+                    _ => 0
+                };
+                colors[(character + colors.len() - shift % colors.len()) % colors.len()]
+            }
+        }
+    }
+}
+
+/// How an [`Attention`] effect changes the look of a message every other half period.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AttentionEffect {
+    /// Hide the text, leaving only the background.
+    Blink,
+    /// Light the whole panel in the color of the text.
+    Flash,
+    /// Swap the colors, drawing the text in the background color over a panel lit in the text color.
+    Invert,
+}
+
+/// Draws the eye to a message by alternating its look, see [`crate::Message::with_attention`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Attention {
+    pub effect: AttentionEffect,
+    /// Length of one cycle, the message looks normal for the first half and changed for the second.
+    pub period: Duration,
+    /// Number of cycles during which the text holds still before it starts moving, `None` to keep going while it moves.
+    pub cycles: Option<u32>,
+}
+
+impl Attention {
+    /// Whether the effect is still going `elapsed` after the message came on screen.
+    pub(crate) fn is_active(&self, elapsed: Duration) -> bool {
+        !self.period.is_zero() && self.cycles.is_none_or(|cycles| elapsed < self.period * cycles)
+    }
+
+    /// Whether the text is held still for the effect.
+    pub(crate) fn holds_text(&self, elapsed: Duration) -> bool {
+        self.cycles.is_some() && self.is_active(elapsed)
+    }
+
+    /// Whether the message has its changed look `elapsed` after it came on screen.
+    pub(crate) fn is_alternate(&self, elapsed: Duration) -> bool {
+        self.is_active(elapsed) && elapsed.as_nanos() % self.period.as_nanos() >= self.period.as_nanos() / 2
+    }
+
+    /// How long after the message came on screen its look changes next, `None` once the effect is over.
+    pub(crate) fn next_change(&self, elapsed: Duration) -> Option<Duration> {
+        if !self.is_active(elapsed) {
+            return None;
+        }
+        let half = (self.period.as_nanos() / 2).max(1);
+        Some(Duration::from_nanos(((elapsed.as_nanos() / half + 1) * half) as u64))
+    }
+}
+
+/// Fully saturated color at `turns` around the color wheel, red at whole turns.
+fn hue(turns: f32) -> Rgb888 {
+    let h = turns.rem_euclid(1.0) * 6.0;
+    let rising = ((h % 1.0) * 255.0).round() as u8;
+    let falling = 255 - rising;
+    match h as u32 {
+        0 => Rgb888::new(255, rising, 0),
+        1 => Rgb888::new(falling, 255, 0),
+        2 => Rgb888::new(0, 255, rising),
+        3 => Rgb888::new(0, falling, 255),
+        4 => Rgb888::new(rising, 0, 255),
+        _ => Rgb888::new(255, 0, falling),
+    }
+}
+
+/// Draw target adapter replacing the color of every pixel drawn through it by `color(point)`.
+pub(crate) struct Recolor<'a, D, F> {
+    target: &'a mut D,
+    color: F,
+}
+
+impl<'a, D, F> Recolor<'a, D, F> where D: DrawTarget, F: Fn(Point) -> D::Color {
+    pub(crate) fn new(target: &'a mut D, color: F) -> Self {
+        Recolor { target, color }
+    }
+}
+
+impl<D, F> Dimensions for Recolor<'_, D, F> where D: DrawTarget {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<D, F> DrawTarget for Recolor<'_, D, F> where D: DrawTarget, F: Fn(Point) -> D::Color {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error> where I: IntoIterator<Item=Pixel<Self::Color>> {
+        let color = &self.color;
+        self.target.draw_iter(pixels.into_iter().map(|Pixel(point, _)| Pixel(point, color(point))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_spans_the_text() {
+        let effect = ColorEffect::Gradient { from: Rgb888::BLACK, to: Rgb888::new(200, 100, 0) };
+        assert_eq!(effect.color_at(0, 5, 0, Duration::ZERO), Rgb888::BLACK);
+        assert_eq!(effect.color_at(2, 5, 0, Duration::ZERO), Rgb888::new(100, 50, 0));
+        assert_eq!(effect.color_at(4, 5, 0, Duration::ZERO), Rgb888::new(200, 100, 0));
+        assert!(!effect.is_animated());
+    }
+
+    #[test]
+    fn rainbow_moves_with_time() {
+        let effect = ColorEffect::Rainbow { spread: 12, cycle: Duration::from_secs(1) };
+        assert_eq!(effect.color_at(0, 10, 0, Duration::ZERO), Rgb888::RED);
+        assert_eq!(effect.color_at(4, 10, 0, Duration::ZERO), Rgb888::GREEN);
+        assert_eq!(effect.color_at(4, 10, 0, Duration::from_secs(1)), Rgb888::GREEN);
+        assert_eq!(effect.color_at(0, 10, 0, Duration::from_millis(500)), Rgb888::CYAN);
+    }
+
+    #[test]
+    fn attention_alternates_for_its_cycles() {
+        let blink = Attention { effect: AttentionEffect::Blink, period: Duration::from_millis(200), cycles: Some(2) };
+        assert!(!blink.is_alternate(Duration::from_millis(50)));
+        assert!(blink.is_alternate(Duration::from_millis(100)));
+        assert!(!blink.is_alternate(Duration::from_millis(200)));
+        assert_eq!(blink.next_change(Duration::from_millis(250)), Some(Duration::from_millis(300)));
+        assert!(blink.holds_text(Duration::from_millis(399)));
+        assert!(!blink.is_alternate(Duration::from_millis(500)));
+        assert_eq!(blink.next_change(Duration::from_millis(400)), None);
+        let endless = Attention { cycles: None, ..blink };
+        assert!(endless.is_alternate(Duration::from_millis(500)));
+        assert!(!endless.holds_text(Duration::ZERO));
+    }
+
+    #[test]
+    fn character_cycle_shifts_by_step() {
+        let effect = ColorEffect::CharacterCycle { colors: vec![Rgb888::RED, Rgb888::GREEN, Rgb888::BLUE], step: Some(Duration::from_millis(100)) };
+        assert_eq!(effect.color_at(0, 10, 1, Duration::ZERO), Rgb888::GREEN);
+        assert_eq!(effect.color_at(0, 10, 1, Duration::from_millis(100)), Rgb888::RED);
+        assert_eq!(effect.color_at(0, 10, 0, Duration::from_millis(100)), Rgb888::BLUE);
+    }
+}
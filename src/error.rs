@@ -0,0 +1,89 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::PoisonError;
+use crate::markup::MarkupError;
+
+/// Everything that can go wrong while showing messages, `E` is the error type of the draw target.
+#[derive(Debug)]
+pub enum PrinterError<E> {
+    /// The font has no glyph for a character of the text.
+    GlyphNotFound(char),
+    /// The font cannot render with the requested colors.
+    UnsupportedFontColor,
+    /// Drawing onto the target failed.
+    DrawTarget(E),
+    /// A thread panicked while holding one of the printer's locks.
+    PoisonedLock,
+    /// The display task stopped without reporting an error.
+    WorkerDied,
+    /// Inline color markup could not be parsed.
+    Markup(MarkupError),
+}
+
+impl PrinterError<Infallible> {
+    /// The same error for a draw target with errors of type `E`, for what went wrong drawing off screen.
+    pub(crate) fn into_target_error<E>(self) -> PrinterError<E> {
+        match self {
+            PrinterError::GlyphNotFound(c) => PrinterError::GlyphNotFound(c),
+            PrinterError::UnsupportedFontColor => PrinterError::UnsupportedFontColor,
+            PrinterError::DrawTarget(never) => match never {},
+            PrinterError::PoisonedLock => PrinterError::PoisonedLock,
+            PrinterError::WorkerDied => PrinterError::WorkerDied,
+            PrinterError::Markup(error) => PrinterError::Markup(error),
+        }
+    }
+}
+
+impl<E> Display for PrinterError<E> where E: Display {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrinterError::GlyphNotFound(c) => write!(f, "font has no glyph for {c:?}"),
+            PrinterError::UnsupportedFontColor => write!(f, "font does not support the requested colors"),
+            PrinterError::DrawTarget(error) => write!(f, "draw target error: {error}"),
+            PrinterError::PoisonedLock => write!(f, "lock poisoned by a panicked thread"),
+            PrinterError::WorkerDied => write!(f, "display task stopped unexpectedly"),
+            PrinterError::Markup(error) => write!(f, "invalid markup: {error}"),
+        }
+    }
+}
+
+impl<E> Error for PrinterError<E> where E: Error + 'static {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PrinterError::DrawTarget(error) => Some(error),
+            PrinterError::Markup(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl<E> From<u8g2_fonts::Error<E>> for PrinterError<E> {
+    fn from(error: u8g2_fonts::Error<E>) -> Self {
+        match error {
+            u8g2_fonts::Error::GlyphNotFound(c) => PrinterError::GlyphNotFound(c),
+            u8g2_fonts::Error::BackgroundColorNotSupported => PrinterError::UnsupportedFontColor,
+            u8g2_fonts::Error::DisplayError(error) => PrinterError::DrawTarget(error),
+        }
+    }
+}
+
+impl<E> From<u8g2_fonts::LookupError> for PrinterError<E> {
+    fn from(error: u8g2_fonts::LookupError) -> Self {
+        match error {
+            u8g2_fonts::LookupError::GlyphNotFound(c) => PrinterError::GlyphNotFound(c),
+        }
+    }
+}
+
+impl<E> From<MarkupError> for PrinterError<E> {
+    fn from(error: MarkupError) -> Self {
+        PrinterError::Markup(error)
+    }
+}
+
+impl<E, T> From<PoisonError<T>> for PrinterError<E> {
+    fn from(_: PoisonError<T>) -> Self {
+        PrinterError::PoisonedLock
+    }
+}
@@ -0,0 +1,19 @@
+use std::sync::{Arc, RwLock};
+use embedded_graphics::pixelcolor::Rgb888;
+use ws2812_esp32_rmt_driver::lib_embedded_graphics::{LedPixelMatrix, Ws2812DrawTarget};
+use ws2812_esp32_rmt_driver::Ws2812Esp32RmtDriverError;
+use crate::error::PrinterError;
+use crate::LedPrinter;
+
+/// A printer on a `W` by `H` WS2812 matrix.
+pub type Ws2812Printer<const W: usize, const H: usize> = LedPrinter<Rgb888, Ws2812Esp32RmtDriverError, Ws2812DrawTarget<LedPixelMatrix<W, H>>>;
+
+impl<const W: usize, const H: usize> LedPrinter<Rgb888, Ws2812Esp32RmtDriverError, Ws2812DrawTarget<LedPixelMatrix<W, H>>> {
+    /// A printer on the matrix wired to `gpio_pin`, driven by RMT `channel`.
+    ///
+    /// The LEDs only change when the target is flushed, e.g. `printer.target().write()?.flush()` from the application loop.
+    pub fn ws2812(channel: u8, gpio_pin: u32, scroll_ms_per_pixel: u16) -> Result<Self, PrinterError<Ws2812Esp32RmtDriverError>> {
+        let target = Ws2812DrawTarget::<LedPixelMatrix<W, H>>::new(channel, gpio_pin).map_err(PrinterError::DrawTarget)?;
+        LedPrinter::new(Arc::new(RwLock::new(target)), scroll_ms_per_pixel)
+    }
+}
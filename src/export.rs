@@ -0,0 +1,264 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::{PixelColor, Rgb888, RgbColor};
+use crate::clock::ManualClock;
+use crate::error::PrinterError;
+use crate::font::Font;
+use crate::message::{Dwell, Message};
+use crate::record::{Frame, RecordingTarget};
+use crate::scroll::ScrollMode;
+use crate::LedPrinter;
+
+/// Scroll steps after which an export without a duration gives up waiting for the first pass.
+const MAX_STEPS: u32 = 10_000;
+
+/// Every frame of an animation with the time it appeared, along with the length of the whole animation.
+type Recording<C> = (Vec<(Frame<C>, Duration)>, Duration);
+
+/// How a single LED is drawn in the exported image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DotStyle {
+    /// Fill the whole cell, like the raw framebuffer.
+    Square,
+    /// A round dot inside the cell, like the LEDs of a matrix.
+    #[default]
+    Round,
+}
+
+/// Everything that can go wrong while exporting an animation.
+#[derive(Debug)]
+pub enum ExportError {
+    Printer(PrinterError<Infallible>),
+    Gif(gif::EncodingError),
+    /// The scaled image does not fit the 16 bit dimensions of a GIF.
+    TooLarge,
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Printer(error) => write!(f, "rendering failed: {error}"),
+            ExportError::Gif(error) => write!(f, "encoding failed: {error}"),
+            ExportError::TooLarge => write!(f, "image too large for a GIF"),
+        }
+    }
+}
+
+impl Error for ExportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExportError::Printer(error) => Some(error),
+            ExportError::Gif(error) => Some(error),
+            ExportError::TooLarge => None
+        }
+    }
+}
+
+impl From<PrinterError<Infallible>> for ExportError {
+    fn from(error: PrinterError<Infallible>) -> Self {
+        ExportError::Printer(error)
+    }
+}
+
+impl From<gif::EncodingError> for ExportError {
+    fn from(error: gif::EncodingError) -> Self {
+        ExportError::Gif(error)
+    }
+}
+
+/// Renders the animation of a message, exactly as a [`LedPrinter`] would show it, into an animated GIF.
+#[derive(Debug, Clone)]
+pub struct GifExporter {
+    matrix: Size,
+    scroll_ms_per_pixel: u16,
+    scroll_mode: ScrollMode,
+    font: Font,
+    scale: u32,
+    dot_style: DotStyle,
+    unlit: Rgb888,
+    duration: Option<Duration>,
+}
+
+impl GifExporter {
+    /// An exporter for a `matrix` sized panel scrolling at `scroll_ms_per_pixel`.
+    pub fn new(matrix: Size, scroll_ms_per_pixel: u16) -> Self {
+        GifExporter {
+            matrix,
+            scroll_ms_per_pixel,
+            scroll_mode: ScrollMode::default(),
+            font: Font::default(),
+            scale: 8,
+            dot_style: DotStyle::default(),
+            unlit: Rgb888::BLACK,
+            duration: None,
+        }
+    }
+
+    pub fn with_scroll_mode(mut self, scroll_mode: ScrollMode) -> Self {
+        self.scroll_mode = scroll_mode;
+        self
+    }
+
+    pub fn with_font(mut self, font: Font) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Size of the square cell each LED is drawn in, in image pixels.
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    pub fn with_dot_style(mut self, dot_style: DotStyle) -> Self {
+        self.dot_style = dot_style;
+        self
+    }
+
+    /// Color of the panel between and behind the dots.
+    pub fn with_unlit_color(mut self, unlit: Rgb888) -> Self {
+        self.unlit = unlit;
+        self
+    }
+
+    /// How much of the animation to export, by default until the message completed its first pass.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Runs the animation of `message` and writes it to `writer` as a looping GIF.
+    pub fn export<C, W>(&self, message: Message<C>, writer: W) -> Result<(), ExportError> where C: PixelColor + Into<Rgb888> + Send + Sync + 'static, W: Write {
+        let (frames, total) = self.record(message)?;
+        self.encode(&frames, total, writer)
+    }
+
+    /// Steps a printer over a headless target one pixel at a time, returning each frame with the time it appeared.
+    fn record<C>(&self, message: Message<C>) -> Result<Recording<C>, ExportError> where C: PixelColor + Send + Sync + 'static {
+        let screen = Arc::new(RwLock::new(RecordingTarget::headless(self.matrix, message.black)));
+        let clock = ManualClock::new();
+        let mut printer = LedPrinter::with_clock(Arc::clone(&screen), self.scroll_ms_per_pixel, Arc::new(clock.clone()))?;
+        printer.set_scroll_mode(self.scroll_mode);
+        printer.set_font(self.font.clone())?;
+        // Static text kept forever never completes a pass, its single frame is the whole animation.
+        let forever = message.dwell == Dwell::Forever;
+        printer.display_message(message)?;
+        printer.sync()?;
+
+        let step = Duration::from_millis(self.scroll_ms_per_pixel.max(1) as u64);
+        let mut frames: Vec<(Frame<C>, Duration)> = Vec::new();
+        let mut elapsed = Duration::ZERO;
+        let mut steps = 0;
+        loop {
+            let recorded = screen.write().map_err(PrinterError::<Infallible>::from)?.take_frames();
+            frames.extend(recorded.into_iter().map(|frame| (frame, elapsed)));
+            let done = match self.duration {
+                Some(duration) => elapsed + step > duration,
+                None => steps >= MAX_STEPS || printer.status()?.showing
+                    .is_none_or(|showing| showing.passes > 0 || forever && showing.direction.is_none())
+            };
+            if done {
+                break;
+            }
+            clock.advance(step);
+            printer.sync()?;
+            elapsed += step;
+            steps += 1;
+        }
+        let total = self.duration.unwrap_or(elapsed + step);
+        Ok((frames, total))
+    }
+
+    fn encode<C, W>(&self, frames: &[(Frame<C>, Duration)], total: Duration, writer: W) -> Result<(), ExportError> where C: PixelColor + Into<Rgb888>, W: Write {
+        let width = u16::try_from(self.matrix.width * self.scale).map_err(|_| ExportError::TooLarge)?;
+        let height = u16::try_from(self.matrix.height * self.scale).map_err(|_| ExportError::TooLarge)?;
+        let mut encoder = gif::Encoder::new(writer, width, height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        // Delays are whole centiseconds, rounding the running time keeps the total exact.
+        let centis = |time: Duration| ((time.as_millis() + 5) / 10) as u64;
+        let mut index = 0;
+        while index < frames.len() {
+            let (frame, start) = &frames[index];
+            // Identical consecutive frames, e.g. static text, become a single longer one.
+            let mut next = index + 1;
+            while next < frames.len() && frames[next].0 == *frame {
+                next += 1;
+            }
+            let end = frames.get(next).map_or(total, |(_, time)| *time);
+            let pixels = self.draw_dots(frame);
+            let mut gif_frame = gif::Frame::from_rgb_speed(width, height, &pixels, 10);
+            gif_frame.delay = (centis(end) - centis(*start)).clamp(1, u16::MAX as u64) as u16;
+            encoder.write_frame(&gif_frame)?;
+            index = next;
+        }
+        Ok(())
+    }
+
+    /// Scales `frame` up into RGB bytes, drawing every LED in the dot style.
+    fn draw_dots<C>(&self, frame: &Frame<C>) -> Vec<u8> where C: PixelColor + Into<Rgb888> {
+        let scale = self.scale as i32;
+        let width = self.matrix.width as i32 * scale;
+        let height = self.matrix.height as i32 * scale;
+        let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let (cell_x, cell_y) = (x % scale, y % scale);
+                let lit = match self.dot_style {
+                    DotStyle::Square => true,
+                    DotStyle::Round => {
+                        // Distances doubled so the centre of even sized cells stays on the grid.
+                        let (dx, dy) = (2 * cell_x + 1 - scale, 2 * cell_y + 1 - scale);
+                        dx * dx + dy * dy <= scale * scale
+                    }
+                };
+                let color = match frame.pixel(Point::new(x / scale, y / scale)) {
+                    Some(color) if lit => color.into(),
+                    _ => self.unlit
+                };
+                pixels.extend_from_slice(&[color.r(), color.g(), color.b()]);
+            }
+        }
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::BinaryColor;
+    use embedded_graphics::text::Alignment;
+    use super::*;
+
+    #[test]
+    fn round_dots_leave_the_corners_unlit() {
+        let frame = Frame::new(Size::new(1, 1), BinaryColor::On);
+        let exporter = GifExporter::new(Size::new(1, 1), 75).with_scale(4).with_unlit_color(Rgb888::BLACK);
+        let pixels = exporter.draw_dots(&frame);
+        assert_eq!(&pixels[0..3], &[0, 0, 0]);
+        assert_eq!(&pixels[(4 + 1) * 3..(4 + 1) * 3 + 3], &[255, 255, 255]);
+    }
+
+    #[test]
+    fn exports_a_gif() {
+        let mut gif = Vec::new();
+        GifExporter::new(Size::new(8, 5), 75)
+            .with_scale(2)
+            .export(Message::new("Hi", Rgb888::WHITE, Rgb888::BLACK), &mut gif)
+            .unwrap();
+        assert_eq!(&gif[0..6], b"GIF89a");
+    }
+
+    #[test]
+    fn static_messages_shown_forever_record_a_single_frame() {
+        let exporter = GifExporter::new(Size::new(8, 5), 75).with_scroll_mode(ScrollMode::Static(Alignment::Left));
+        let message = Message::new("Hi", Rgb888::WHITE, Rgb888::BLACK).with_dwell(Dwell::Forever);
+        let (frames, total) = exporter.record(message).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(total, Duration::from_millis(75));
+    }
+}
@@ -0,0 +1,209 @@
+use std::borrow::Cow;
+use std::fmt::{Debug, Formatter};
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle};
+use embedded_graphics::pixelcolor::{BinaryColor, PixelColor};
+use embedded_graphics::prelude::{Point, Size};
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+use u8g2_fonts::FontRenderer;
+use u8g2_fonts::types::{FontColor, VerticalPosition};
+use crate::record::Frame;
+
+/// Characters used to find the topmost row a font actually draws into.
+const METRIC_REFERENCE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789()[]{}|";
+
+/// The topmost row of the character cells `font` draws into for `text`, `None` if the text has no pixels at all.
+fn mono_top(font: &'static MonoFont<'static>, text: &str) -> Option<i32> {
+    let advance = font.character_size.width + font.character_spacing;
+    let size = Size::new(text.chars().count() as u32 * advance, font.character_size.height);
+    let mut frame = Frame::new(size, BinaryColor::Off);
+    let _ = Text::with_baseline(text, Point::zero(), MonoTextStyle::new(font, BinaryColor::On), Baseline::Top).draw(&mut frame);
+    (0..size.height as i32).find(|&y| (0..size.width as i32).any(|x| frame.pixel(Point::new(x, y)) == Some(BinaryColor::On)))
+}
+
+#[derive(Clone)]
+enum FontKind {
+    U8g2(FontRenderer),
+    Mono(&'static MonoFont<'static>),
+}
+
+/// Font used by [`crate::LedPrinter`] to render messages.
+///
+/// Text is drawn so the tallest glyph of the font touches the top row of the target.
+#[derive(Clone)]
+pub struct Font {
+    kind: FontKind,
+    y_offset: i32,
+    fallback_glyph: Option<char>,
+}
+
+impl Font {
+    /// Any u8g2 font, e.g. `Font::u8g2::<u8g2_fonts::fonts::u8g2_font_5x8_tr>()`.
+    pub fn u8g2<F: u8g2_fonts::Font>() -> Self {
+        let top = FontRenderer::new::<F>()
+            .with_ignore_unknown_chars(true)
+            .get_rendered_dimensions(METRIC_REFERENCE, Point::zero(), VerticalPosition::Top)
+            .ok()
+            .and_then(|dimensions| dimensions.bounding_box)
+            .map(|bounding_box| bounding_box.top_left.y)
+            .unwrap_or(0);
+        Font {
+            kind: FontKind::U8g2(FontRenderer::new::<F>()),
+            y_offset: -top,
+            fallback_glyph: None,
+        }
+    }
+
+    /// An embedded-graphics monospaced font, e.g. `Font::mono(&embedded_graphics::mono_font::ascii::FONT_5X8)`.
+    pub fn mono(font: &'static MonoFont<'static>) -> Self {
+        Font {
+            kind: FontKind::Mono(font),
+            y_offset: -mono_top(font, METRIC_REFERENCE).unwrap_or(0),
+            fallback_glyph: None,
+        }
+    }
+
+    /// Overrides the vertical offset derived from the font metrics.
+    pub fn with_y_offset(mut self, y_offset: i32) -> Self {
+        self.y_offset = y_offset;
+        self
+    }
+
+    /// Draws `glyph` in place of characters missing from the font, instead of failing with [`crate::PrinterError::GlyphNotFound`].
+    ///
+    /// Monospaced fonts always draw their own replacement glyph for unknown characters.
+    pub fn with_fallback_glyph(mut self, glyph: Option<char>) -> Self {
+        self.fallback_glyph = glyph;
+        self
+    }
+
+    fn has_glyph(&self, c: char) -> bool {
+        match &self.kind {
+            FontKind::U8g2(renderer) => renderer
+                .get_rendered_dimensions(c.encode_utf8(&mut [0; 4]), Point::zero(), VerticalPosition::Top)
+                .is_ok(),
+            FontKind::Mono(_) => true,
+        }
+    }
+
+    /// `text` with every character missing from the font swapped for the fallback glyph, if there is one.
+    pub(crate) fn prepare<'a>(&self, text: &'a str) -> Result<Cow<'a, str>, u8g2_fonts::LookupError> {
+        let fallback = match self.fallback_glyph {
+            Some(fallback) => fallback,
+            None => return Ok(Cow::Borrowed(text))
+        };
+        if text.chars().all(|c| self.has_glyph(c)) {
+            return Ok(Cow::Borrowed(text));
+        }
+        if !self.has_glyph(fallback) {
+            return Err(u8g2_fonts::LookupError::GlyphNotFound(fallback));
+        }
+        Ok(Cow::Owned(text.chars().map(|c| if self.has_glyph(c) { c } else { fallback }).collect()))
+    }
+
+    /// Width in pixels of `text`, `None` if nothing would be drawn.
+    pub(crate) fn text_width(&self, text: &str) -> Result<Option<u32>, u8g2_fonts::LookupError> {
+        match &self.kind {
+            FontKind::U8g2(renderer) => Ok(renderer
+                .get_rendered_dimensions(text, Point::zero(), VerticalPosition::Top)?
+                .bounding_box
+                .map(|bounding_box| bounding_box.size.width)),
+            FontKind::Mono(font) => {
+                if mono_top(font, text).is_none() {
+                    return Ok(None);
+                }
+                let characters = text.chars().count() as u32;
+                Ok(Some(characters * font.character_size.width + (characters - 1) * font.character_spacing))
+            }
+        }
+    }
+
+    /// How far the next glyph after `text` is drawn from the start of `text`.
+    pub(crate) fn advance(&self, text: &str) -> Result<i32, u8g2_fonts::LookupError> {
+        match &self.kind {
+            FontKind::U8g2(renderer) => Ok(renderer.get_rendered_dimensions(text, Point::zero(), VerticalPosition::Top)?.advance.x),
+            FontKind::Mono(font) => Ok(text.chars().count() as i32 * (font.character_size.width + font.character_spacing) as i32)
+        }
+    }
+
+    /// Draws `text` with its left edge at `x`.
+    pub(crate) fn draw<C, D>(&self, text: &str, x: i32, color: C, target: &mut D) -> Result<(), u8g2_fonts::Error<D::Error>> where C: PixelColor, D: DrawTarget<Color=C> {
+        let position = Point::new(x, self.y_offset);
+        match &self.kind {
+            FontKind::U8g2(renderer) => {
+                renderer.render(text, position, VerticalPosition::Top, FontColor::Transparent(color), target)?;
+            }
+            FontKind::Mono(font) => {
+                Text::with_baseline(text, position, MonoTextStyle::new(font, color), Baseline::Top)
+                    .draw(target)
+                    .map_err(u8g2_fonts::Error::DisplayError)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Font::u8g2::<u8g2_fonts::fonts::u8g2_font_standardized3x5_tr>()
+    }
+}
+
+impl Debug for Font {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            FontKind::U8g2(_) => "u8g2",
+            FontKind::Mono(_) => "mono",
+        };
+        f.debug_struct("Font")
+            .field("kind", &kind)
+            .field("y_offset", &self.y_offset)
+            .field("fallback_glyph", &self.fallback_glyph)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_glyphs_use_the_fallback() {
+        let font = Font::default();
+        assert!(font.prepare("café").is_ok());
+        assert!(matches!(font.text_width("café"), Err(u8g2_fonts::LookupError::GlyphNotFound('é'))));
+        let font = font.with_fallback_glyph(Some('?'));
+        assert_eq!(font.prepare("café").unwrap(), "caf?");
+        assert!(matches!(font.prepare("cafe").unwrap(), Cow::Borrowed("cafe")));
+        assert_eq!(font.text_width("").unwrap(), None);
+    }
+
+    /// Whether `font` draws anything into the top row of a 5 pixel high frame.
+    fn touches_the_top(font: &Font) -> bool {
+        let mut frame = Frame::new(Size::new(40, 5), BinaryColor::Off);
+        font.draw("Hb[", 0, BinaryColor::On, &mut frame).unwrap();
+        (0..40).any(|x| frame.pixel(Point::new(x, 0)) == Some(BinaryColor::On))
+    }
+
+    #[test]
+    fn offsets_come_from_the_font_metrics() {
+        assert!(touches_the_top(&Font::default()));
+        assert!(touches_the_top(&Font::mono(&embedded_graphics::mono_font::ascii::FONT_4X6)));
+        let tall = Font::mono(&embedded_graphics::mono_font::ascii::FONT_6X10);
+        assert!(touches_the_top(&tall));
+        assert!(tall.y_offset < 0);
+        let lowered = tall.y_offset + 1;
+        assert!(!touches_the_top(&tall.with_y_offset(lowered)));
+    }
+
+    #[test]
+    fn mono_fonts_measure_like_u8g2() {
+        let font = Font::mono(&embedded_graphics::mono_font::ascii::FONT_4X6);
+        assert_eq!(font.text_width("").unwrap(), None);
+        assert_eq!(font.text_width("  ").unwrap(), None);
+        assert_eq!(font.text_width("a b").unwrap(), Some(12));
+        assert_eq!(font.advance("a b").unwrap(), 12);
+        assert_eq!(Font::default().text_width(" ").unwrap(), None);
+    }
+}
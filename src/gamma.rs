@@ -0,0 +1,156 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::Dimensions;
+use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+
+/// Gamma of typical WS2812 LEDs, a linear ramp of values looks far too bright at the low end without it.
+pub const DEFAULT_GAMMA: f32 = 2.8;
+
+/// Shared brightness of a [`GammaTarget`], which can be changed while the display task holds the target.
+///
+/// The level set by the application is combined with the level of the [`crate::Schedule`], if any.
+#[derive(Debug, Clone)]
+pub struct Brightness {
+    level: Arc<AtomicU8>,
+    scheduled: Arc<AtomicU8>,
+}
+
+impl Brightness {
+    fn new() -> Self {
+        Brightness {
+            level: Arc::new(AtomicU8::new(u8::MAX)),
+            scheduled: Arc::new(AtomicU8::new(u8::MAX)),
+        }
+    }
+
+    /// Sets the brightness, from 0 for off to 255 for full.
+    pub fn set(&self, level: u8) {
+        self.level.store(level, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u8 {
+        self.level.load(Ordering::Relaxed)
+    }
+
+    /// Dims on top of the level set by the application, returns whether anything changed.
+    pub(crate) fn set_scheduled(&self, level: u8) -> bool {
+        self.scheduled.swap(level, Ordering::Relaxed) != level
+    }
+
+    /// What pixels are drawn at, both levels combined.
+    pub fn effective(&self) -> u8 {
+        ((self.get() as u32 * self.scheduled.load(Ordering::Relaxed) as u32 + 127) / 255) as u8
+    }
+}
+
+/// Wraps a draw target, scaling every pixel by a brightness level and correcting it for the gamma of the LEDs.
+///
+/// Colors are given as they should look, so `Rgb888::new(128, 128, 128)` appears about half as bright as white.
+/// The brightness can be changed through a shared reference, e.g. while the display task holds the target,
+/// see [`crate::LedPrinter::set_brightness`].
+#[derive(Debug)]
+pub struct GammaTarget<Target> {
+    target: Target,
+    table: [u8; 256],
+    brightness: Brightness,
+}
+
+impl<Target> GammaTarget<Target> {
+    /// Wraps `target` at full brightness with [`DEFAULT_GAMMA`].
+    pub fn new(target: Target) -> Self {
+        GammaTarget {
+            target,
+            table: gamma_table(DEFAULT_GAMMA),
+            brightness: Brightness::new(),
+        }
+    }
+
+    /// Replaces the gamma, 1.0 passes colors through unchanged.
+    pub fn with_gamma(mut self, gamma: f32) -> Self {
+        self.table = gamma_table(gamma);
+        self
+    }
+
+    /// Sets the brightness, from 0 for off to 255 for full. Applies to pixels drawn from now on.
+    pub fn set_brightness(&self, brightness: u8) {
+        self.brightness.set(brightness);
+    }
+
+    pub fn brightness(&self) -> u8 {
+        self.brightness.get()
+    }
+
+    /// A handle on the brightness, e.g. for a [`crate::Schedule`] to dim the target.
+    pub fn brightness_control(&self) -> Brightness {
+        self.brightness.clone()
+    }
+
+    pub fn inner(&self) -> &Target {
+        &self.target
+    }
+
+    /// The wrapped target, e.g. to flush it to the LEDs.
+    pub fn inner_mut(&mut self) -> &mut Target {
+        &mut self.target
+    }
+
+    pub fn into_inner(self) -> Target {
+        self.target
+    }
+
+    fn correct<C>(&self, color: C) -> C where C: Into<Rgb888> + From<Rgb888> {
+        let color: Rgb888 = color.into();
+        let brightness = self.brightness.effective() as u32;
+        let channel = |value: u8| self.table[((value as u32 * brightness + 127) / 255) as usize];
+        C::from(Rgb888::new(channel(color.r()), channel(color.g()), channel(color.b())))
+    }
+}
+
+fn gamma_table(gamma: f32) -> [u8; 256] {
+    let mut table = [0; 256];
+    for (value, entry) in table.iter_mut().enumerate() {
+        *entry = ((value as f32 / 255.0).powf(gamma) * 255.0).round() as u8;
+    }
+    table
+}
+
+impl<Target> Dimensions for GammaTarget<Target> where Target: DrawTarget {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<Target> DrawTarget for GammaTarget<Target> where Target: DrawTarget, Target::Color: Into<Rgb888> + From<Rgb888> {
+    type Color = Target::Color;
+    type Error = Target::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error> where I: IntoIterator<Item=Pixel<Self::Color>> {
+        let corrected: Vec<Pixel<Self::Color>> = pixels.into_iter().map(|Pixel(point, color)| Pixel(point, self.correct(color))).collect();
+        self.target.draw_iter(corrected)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let color = self.correct(color);
+        self.target.clear(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::geometry::{Point, Size};
+    use crate::record::Frame;
+    use super::*;
+
+    #[test]
+    fn scales_then_corrects() {
+        let mut target = GammaTarget::new(Frame::new(Size::new(1, 1), Rgb888::BLACK)).with_gamma(2.0);
+        target.draw_iter([Pixel(Point::zero(), Rgb888::new(255, 128, 0))]).unwrap();
+        assert_eq!(target.inner().pixel(Point::zero()), Some(Rgb888::new(255, 64, 0)));
+        target.set_brightness(128);
+        target.clear(Rgb888::WHITE).unwrap();
+        assert_eq!(target.inner().pixel(Point::zero()), Some(Rgb888::new(64, 64, 64)));
+    }
+}
@@ -1,149 +1,911 @@
+mod clock;
+mod effect;
+mod error;
+#[cfg(feature = "esp32")]
+mod esp32;
+#[cfg(feature = "gif")]
+mod export;
+mod font;
+mod gamma;
+mod markup;
+mod message;
+mod playlist;
+mod power;
+mod record;
+mod schedule;
+mod scroll;
+mod status;
+mod task;
+mod terminal;
+mod transition;
+
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use effect::{Attention, AttentionEffect, ColorEffect};
+pub use error::PrinterError;
+#[cfg(feature = "esp32")]
+pub use esp32::Ws2812Printer;
+#[cfg(feature = "gif")]
+pub use export::{DotStyle, ExportError, GifExporter};
+pub use font::Font;
+pub use gamma::{Brightness, GammaTarget, DEFAULT_GAMMA};
+pub use markup::MarkupError;
+pub use message::{Dwell, Message, MessageId, Priority};
+pub use power::{CurrentLimiter, CurrentMeter, CurrentModel, CurrentReading};
+#[cfg(any(test, feature = "test-support"))]
+pub use record::{assert_golden, BLESS_ENV};
+pub use record::{frames_to_grid, Frame, RecordingTarget};
+pub use schedule::{Schedule, ScheduledLevel, Weekday};
+pub use scroll::{ScrollDirection, ScrollMode};
+pub use status::{PrinterStatus, ShowingStatus};
+pub use terminal::TerminalTarget;
+pub use transition::Transition;
+
 use std::error::Error;
-use std::fmt::Display;
-use std::ops::DerefMut;
-use std::os;
 use std::sync::{Arc, Mutex, RwLock};
-use std::thread::{JoinHandle, sleep, spawn};
-use std::time::{Duration, Instant};
+use std::sync::mpsc::{channel, Sender};
+use std::thread::{JoinHandle, spawn};
 use embedded_graphics::draw_target::DrawTarget;
-use embedded_graphics::mono_font::MonoTextStyle;
-use embedded_graphics::pixelcolor::PixelColor;
-use embedded_graphics::prelude::Point;
-use u8g2_fonts::{FontRenderer, U8g2TextStyle};
-use u8g2_fonts::types::{FontColor, VerticalPosition};
-use ws2812_esp32_rmt_driver::lib_embedded_graphics::{LedPixelMatrix, Ws2812DrawTarget};
-use ws2812_esp32_rmt_driver::{Ws2812Esp32RmtDriver, Ws2812Esp32RmtDriverError};
-use ws2812_esp32_rmt_driver::driver::color::{LedPixelColor, LedPixelColorRgbw32};
+use embedded_graphics::pixelcolor::{PixelColor, Rgb888, RgbColor};
+use embedded_graphics::text::Alignment;
+use crate::playlist::Playlist;
+use crate::task::{Command, DisplayTask, TaskSettings, TaskState};
 
 #[derive(Debug)]
 pub struct LedPrinter<C, E, Target> where Target: DrawTarget<Color=C, Error=E>, C: PixelColor, E: Error {
     draw_target: Arc<RwLock<Target>>,
-    scroll_spp_ms: u16,
+    settings: TaskSettings,
+    clock: Arc<dyn Clock>,
+    current_meter: Option<CurrentMeter>,
+    paused: bool,
+    playlist: Arc<Mutex<Playlist<C>>>,
     display_task: Option<JoinHandle<()>>,
-    display_task_controller: Arc<Mutex<bool>>
+    display_task_commands: Option<Sender<Command>>,
+    display_task_state: Arc<Mutex<TaskState<C, E>>>
 }
 
-#[derive(Debug, Copy, Clone)]
-enum Direction {
-    Left,
-    Right
+impl<C, E, Target> LedPrinter<C, E, Target> where Target: DrawTarget<Color=C, Error=E>, C: PixelColor, E: Error {
+    fn stop_task(&mut self) {
+        if let Some(commands) = self.display_task_commands.take() {
+            let _ = commands.send(Command::Stop);
+        }
+        if let Some(handle) = self.display_task.take() {
+            let _ = handle.join();
+        }
+    }
 }
 
-impl<C, E, Target> LedPrinter<C, E, Target> where Target: DrawTarget<Color=C, Error=E> + Send + Sync + 'static, C: PixelColor + Send + 'static, E: Error {
-    pub fn new(target: Arc<RwLock<Target>>, scroll_ms_per_pixel: u16) -> Result<Self, E> {
+impl<C, E, Target> Drop for LedPrinter<C, E, Target> where Target: DrawTarget<Color=C, Error=E>, C: PixelColor, E: Error {
+    fn drop(&mut self) {
+        self.stop_task();
+    }
+}
+
+impl<C, E, Target> LedPrinter<C, E, Target> where Target: DrawTarget<Color=C, Error=E> + Send + Sync + 'static, C: PixelColor + Send + 'static, E: Error + Send + 'static {
+    pub fn new(target: Arc<RwLock<Target>>, scroll_ms_per_pixel: u16) -> Result<Self, PrinterError<E>> {
+        LedPrinter::with_clock(target, scroll_ms_per_pixel, Arc::new(SystemClock))
+    }
+
+    /// Like [`LedPrinter::new`], with the display task timing frames, dwells and expiries by `clock`.
+    ///
+    /// The time to live of a message, see [`Message::with_time_to_live`], also starts by `clock` when the message is handed over.
+    pub fn with_clock(target: Arc<RwLock<Target>>, scroll_ms_per_pixel: u16, clock: Arc<dyn Clock>) -> Result<Self, PrinterError<E>> {
+        drop(target.read()?);
         Ok(LedPrinter{
             draw_target: target,
-            scroll_spp_ms: scroll_ms_per_pixel,
+            settings: TaskSettings {
+                scroll_spp_ms: scroll_ms_per_pixel,
+                scroll_mode: ScrollMode::default(),
+                static_when_fits: None,
+                font: Font::default(),
+                schedule: None,
+            },
+            clock,
+            current_meter: None,
+            paused: false,
+            playlist: Arc::new(Mutex::new(Playlist::new())),
             display_task: None,
-            display_task_controller: Arc::new(Mutex::new(false))
+            display_task_commands: None,
+            display_task_state: Arc::new(Mutex::new(TaskState::new()))
         })
     }
 
-    fn text_display_task(target: Arc<RwLock<impl DrawTarget<Color=C, Error=E>>>, spp: u16, task_controller: Arc<Mutex<bool>>, to_display: String, black: C, color: C){
-        let renderer = FontRenderer::new::<u8g2_fonts::fonts::u8g2_font_standardized3x5_tr>();
-        let width = renderer.get_rendered_dimensions(to_display.as_str(), Point::zero(), VerticalPosition::Top).unwrap().bounding_box.unwrap().size.width as i32;
-        let mut x_pos = 0;
-        let mut direction = Direction::Right;
-        let step_period = 10;
-        let mut previous_update = 0;
-        let spp = spp as u64;
-        let mut running = true;
-        let mut target_locked = target.write().unwrap();
-        target_locked.clear(black);
-        renderer.render(&*to_display, Point::new(-x_pos, -1), VerticalPosition::Top, FontColor::Transparent(color), target_locked.deref_mut()).unwrap();
-        drop(target_locked);
-        while running {
-            if previous_update * step_period > spp {
-                target_locked = target.write().unwrap();
-                target_locked.clear(black);
-                renderer.render(&*to_display, Point::new(-x_pos, -1), VerticalPosition::Top, FontColor::Transparent(color), target_locked.deref_mut()).unwrap();
-                drop(target_locked);
-                match direction {
-                    Direction::Left => {
-                        if x_pos == 0 {
-                            direction = Direction::Right;
-                        } else {
-                            x_pos -= 1;
-                        }
-                    },
-                    Direction::Right => {
-                        if x_pos == width {
-                            direction = Direction::Left;
-                        } else {
-                            x_pos += 1;
-                        }
-                    }
-                }
-                previous_update = 0;
-            }else {
-                previous_update += 1;
+    /// Hands the current settings to the display task, which restarts the message on screen with them.
+    fn settings_changed(&mut self) {
+        if let Some(commands) = &self.display_task_commands {
+            let _ = commands.send(Command::Settings(self.settings.clone()));
+        }
+    }
+
+    /// Sets how many milliseconds the text takes to move by one pixel, the text carries on from where it is.
+    pub fn set_scroll_speed(&mut self, scroll_ms_per_pixel: u16) {
+        self.settings.scroll_spp_ms = scroll_ms_per_pixel;
+        if let Some(commands) = &self.display_task_commands {
+            let _ = commands.send(Command::Speed(scroll_ms_per_pixel));
+        }
+    }
+
+    /// Sets how the text moves across the target.
+    pub fn set_scroll_mode(&mut self, mode: ScrollMode) {
+        self.settings.scroll_mode = mode;
+        self.settings_changed();
+    }
+
+    /// When set, text narrower than the target is drawn once with the given alignment instead of scrolling.
+    pub fn set_static_when_fits(&mut self, alignment: Option<Alignment>) {
+        self.settings.static_when_fits = alignment;
+        self.settings_changed();
+    }
+
+    /// Sets the font used to render text.
+    ///
+    /// Every queued message is checked against the new font first, as [`LedPrinter::display`] does,
+    /// and the font is left unchanged if one of them has characters it cannot render.
+    pub fn set_font(&mut self, font: Font) -> Result<(), PrinterError<E>> {
+        for message in self.playlist.lock()?.messages() {
+            let text = font.prepare(&message.text)?;
+            font.text_width(&text)?;
+        }
+        self.settings.font = font;
+        self.settings_changed();
+        Ok(())
+    }
+
+    /// Dims or blanks the display at the times given by `schedule`, `None` to always run at full brightness.
+    pub fn set_schedule(&mut self, schedule: Option<Schedule>) {
+        self.settings.schedule = schedule;
+        self.settings_changed();
+    }
+
+    /// Checks up front that the display task will be able to render `message`, and starts its time to live.
+    ///
+    /// Characters missing from the font are an error unless the font has a fallback glyph, see [`Font::with_fallback_glyph`].
+    /// Empty text, or text made only of glyphs without pixels, is fine and blanks the screen with the message background.
+    fn admit(&self, message: Message<C>) -> Result<Message<C>, PrinterError<E>> {
+        let text = self.settings.font.prepare(&message.text)?;
+        self.settings.font.text_width(&text)?;
+        Ok(message.queued_at(self.clock.now()))
+    }
+
+    /// Spawns the display task unless it is already running.
+    ///
+    /// If the task died since the last call, its error is returned instead and the next call spawns a new task.
+    fn start_task(&mut self) -> Result<(), PrinterError<E>> {
+        if let Some(handle) = &self.display_task {
+            if !handle.is_finished() {
+                return Ok(());
             }
-            sleep(Duration::from_millis(step_period));
-            let controller = task_controller.lock().unwrap();
-            running = *controller;
+            return Err(self.reap_task());
+        }
+        self.display_task_state.lock()?.error = None;
+
+        let (commands, receiver) = channel();
+        let draw_target = Arc::clone(&self.draw_target);
+        let state = Arc::clone(&self.display_task_state);
+        let playlist = Arc::clone(&self.playlist);
+        let settings = self.settings.clone();
+        let clock = Arc::clone(&self.clock);
+        let paused = self.paused;
+        self.display_task_commands = Some(commands);
+        let _ = self.display_task.insert(spawn(move ||{DisplayTask::new(draw_target, settings, clock, receiver, state, playlist, paused).run()}));
+        Ok(())
+    }
+
+    /// Sends `command` to the display task, spawning it first if needed.
+    fn send(&mut self, command: Command) -> Result<(), PrinterError<E>> {
+        self.start_task()?;
+        let sent = self.display_task_commands.as_ref().is_some_and(|commands| commands.send(command).is_ok());
+        if !sent {
+            return Err(self.reap_task());
         }
+        Ok(())
+    }
+
+    /// Blocks until the display task has handled every earlier call and drawn everything due by its clock.
+    ///
+    /// Does nothing while the display task is not running.
+    pub fn sync(&mut self) -> Result<(), PrinterError<E>> {
+        if self.display_task.is_none() {
+            return Ok(());
+        }
+        let (reply, done) = channel();
+        self.send(Command::Sync(reply))?;
+        if done.recv().is_err() {
+            return Err(self.reap_task());
+        }
+        Ok(())
+    }
+
+    /// The target the display task draws onto, e.g. to flush it to hardware.
+    pub fn target(&self) -> Arc<RwLock<Target>> {
+        Arc::clone(&self.draw_target)
     }
 
-    pub fn display(&mut self, text: &str, color: C, black: C) {
+    /// Reports the estimate of a [`CurrentLimiter`] somewhere in the target in [`PrinterStatus::current`].
+    pub fn set_current_meter(&mut self, meter: Option<CurrentMeter>) {
+        self.current_meter = meter;
+    }
+
+    /// Whether the display task is alive.
+    pub fn is_running(&self) -> bool {
+        self.display_task.as_ref().is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Hands over why the display task stopped, `None` while it is running or was never started.
+    ///
+    /// The error can be taken only once, [`PrinterStatus::error`] shows it until then.
+    /// The next call that needs the display task starts a new one.
+    pub fn take_error(&mut self) -> Option<PrinterError<E>> {
+        if self.is_running() || self.display_task.is_none() {
+            return None;
+        }
+        Some(self.reap_task())
+    }
+
+    /// Joins a display task known to be stopping and takes its error.
+    ///
+    /// Its channels close before it stores the error, so this waits for the thread rather than checking [`LedPrinter::is_running`].
+    fn reap_task(&mut self) -> PrinterError<E> {
+        self.display_task_commands = None;
         if let Some(handle) = self.display_task.take() {
-            let mut task_controller = self.display_task_controller.lock().expect("Failed to lock");
-            *task_controller = false;
-            drop(task_controller);
-            handle.join().unwrap();
+            let _ = handle.join();
         }
-        let mut task_controller = self.display_task_controller.lock().expect("Failed to lock");
+        let error = match self.display_task_state.lock() {
+            Ok(mut state) => state.error.take(),
+            Err(_) => Some(PrinterError::PoisonedLock)
+        };
+        error.unwrap_or(PrinterError::WorkerDied)
+    }
 
-        *task_controller = true;
+    /// What the display task is showing, as of the last frame it drew.
+    pub fn status(&self) -> Result<PrinterStatus<C>, PrinterError<E>> {
+        let (playlist_len, pending_interrupts) = {
+            let playlist = self.playlist.lock()?;
+            (playlist.len(), playlist.pending_interrupts())
+        };
+        let state = self.display_task_state.lock()?;
+        Ok(PrinterStatus {
+            running: self.is_running(),
+            paused: self.paused,
+            showing: state.showing.clone(),
+            scheduled: state.scheduled,
+            playlist_len,
+            pending_interrupts,
+            current: self.current_meter.as_ref().and_then(CurrentMeter::reading),
+            error: state.error.as_ref().map(ToString::to_string),
+        })
+    }
 
-        let draw_target = Arc::clone(&self.draw_target);
-        let task_controller = Arc::clone(&self.display_task_controller);
-        let text = text.to_string();
-        let spp = self.scroll_spp_ms;
-        let _ = self.display_task.insert(spawn(move ||{Self::text_display_task(draw_target, spp, task_controller, text, black.clone(), color.clone())}));
+    /// Replaces the whole playlist with `text`.
+    pub fn display(&mut self, text: &str, color: C, black: C) -> Result<(), PrinterError<E>> {
+        self.display_message(Message::new(text, color, black).with_dwell(Dwell::Forever))?;
+        Ok(())
+    }
+
+    /// Like [`LedPrinter::display`], with inline colors as in [`Message::markup`], e.g. `BUILD [red]FAILED[/]`.
+    pub fn display_markup(&mut self, markup: &str, color: C, black: C) -> Result<(), PrinterError<E>> where C: From<Rgb888> {
+        self.display_message(Message::markup(markup, color, black)?.with_dwell(Dwell::Forever))?;
+        Ok(())
+    }
+
+    /// Like [`LedPrinter::display`], with the text colored by `effect`, e.g. a gradient or a rainbow.
+    pub fn display_with_effect(&mut self, text: &str, effect: ColorEffect, black: C) -> Result<(), PrinterError<E>> where C: From<Rgb888> {
+        let color = C::from(Rgb888::WHITE);
+        self.display_message(Message::new(text, color, black).with_color_effect(effect).with_dwell(Dwell::Forever))?;
+        Ok(())
+    }
+
+    /// Like [`LedPrinter::display`], for a message built with [`Message`] options such as an expiry.
+    pub fn display_message(&mut self, message: Message<C>) -> Result<MessageId, PrinterError<E>> {
+        let message = self.admit(message)?;
+        let id = {
+            let mut playlist = self.playlist.lock()?;
+            playlist.clear();
+            playlist.add(message)
+        };
+        self.send(Command::Refresh)?;
+        Ok(id)
+    }
+
+    /// Sets the message shown once the playlist runs out, e.g. after every message expired.
+    ///
+    /// Without one the screen is cleared with the background of the last message.
+    pub fn set_idle_message(&mut self, message: Option<Message<C>>) -> Result<(), PrinterError<E>> {
+        let message = message.map(|message| self.admit(message)).transpose()?;
+        self.playlist.lock()?.set_idle(message);
+        self.send(Command::Refresh)
+    }
+
+    /// Appends `message` to the playlist, the display task rotates through it.
+    pub fn add_message(&mut self, message: Message<C>) -> Result<MessageId, PrinterError<E>> {
+        let message = self.admit(message)?;
+        let id = self.playlist.lock()?.add(message);
+        self.send(Command::Refresh)?;
+        Ok(id)
+    }
+
+    /// Removes a message from the playlist, returning it if it was still there.
+    pub fn remove_message(&mut self, id: MessageId) -> Result<Option<Message<C>>, PrinterError<E>> {
+        let removed = self.playlist.lock()?.remove(id);
+        self.send(Command::Refresh)?;
+        Ok(removed)
+    }
+
+    /// Swaps the message with the given id for `message`, keeping its place in the rotation.
+    ///
+    /// If the message is on screen it is redrawn immediately.
+    pub fn replace_message(&mut self, id: MessageId, message: Message<C>) -> Result<Option<Message<C>>, PrinterError<E>> {
+        let message = self.admit(message)?;
+        let replaced = self.playlist.lock()?.replace(id, message);
+        self.send(Command::Refresh)?;
+        Ok(replaced)
+    }
+
+    /// Freezes the current frame, scrolling and dwell times stand still until [`LedPrinter::resume`].
+    pub fn pause(&mut self) -> Result<(), PrinterError<E>> {
+        self.paused = true;
+        if self.display_task.is_some() {
+            self.send(Command::Pause)?;
+        }
+        Ok(())
+    }
+
+    /// Continues after [`LedPrinter::pause`] or [`LedPrinter::stop`].
+    pub fn resume(&mut self) -> Result<(), PrinterError<E>> {
+        self.paused = false;
+        self.send(Command::Resume)
+    }
+
+    /// Stops the display task and leaves the last frame on screen, the playlist is kept for [`LedPrinter::resume`].
+    pub fn stop(&mut self) -> Result<(), PrinterError<E>> {
+        self.stop_task();
+        Ok(())
+    }
+
+    /// Stops the display task, forgets every message and pending interrupt, and fills the screen with `black`.
+    pub fn clear(&mut self, black: C) -> Result<(), PrinterError<E>> {
+        self.stop_task();
+        {
+            let mut playlist = self.playlist.lock()?;
+            playlist.clear();
+            playlist.clear_interrupts();
+        }
+        self.display_task_state.lock()?.showing = None;
+        self.draw_target.write()?.clear(black).map_err(PrinterError::DrawTarget)
+    }
+
+    /// Shows `message` right away if its priority beats whatever is on screen, otherwise as soon as that finishes.
+    ///
+    /// Once the message's dwell is over, the interrupted message resumes at the scroll position it left off.
+    pub fn interrupt(&mut self, message: Message<C>) -> Result<(), PrinterError<E>> {
+        let message = self.admit(message)?;
+        self.playlist.lock()?.push_interrupt(message);
+        self.send(Command::Refresh)
+    }
+}
+
+impl<C, E, Target> LedPrinter<C, E, GammaTarget<Target>> where Target: DrawTarget<Color=C, Error=E> + Send + Sync + 'static, C: PixelColor + Into<Rgb888> + From<Rgb888> + Send + 'static, E: Error + Send + 'static {
+    /// Sets the brightness of the [`GammaTarget`], from 0 for off to 255 for full, redrawing the frame on screen right away.
+    pub fn set_brightness(&mut self, brightness: u8) -> Result<(), PrinterError<E>> {
+        self.draw_target.read()?.set_brightness(brightness);
+        if self.is_running() {
+            self.send(Command::Redraw)?;
+        }
+        Ok(())
+    }
+
+    pub fn brightness(&self) -> Result<u8, PrinterError<E>> {
+        Ok(self.draw_target.read()?.brightness())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::ops::Deref;
-    use embedded_graphics::pixelcolor::Rgb888;
-    use embedded_graphics::prelude::Size;
-    use embedded_graphics_simulator::{OutputSettings, OutputSettingsBuilder, SimulatorEvent, Window};
-    use embedded_graphics_simulator::sdl2::Keycode;
-    use ws2812_esp32_rmt_driver::driver::color::LedPixelColorImpl;
-    use ws2812_esp32_rmt_driver::lib_embedded_graphics::{LedPixelDrawTarget, LedPixelShape};
+    use std::convert::Infallible;
+    use std::time::{Duration, Instant, UNIX_EPOCH};
+    use embedded_graphics::pixelcolor::BinaryColor;
+    use embedded_graphics::prelude::{OriginDimensions, Pixel, Point, Size};
     use super::*;
 
+    type Recorder<C> = RecordingTarget<Frame<C>>;
+    type TestPrinter<C, Target> = LedPrinter<C, Infallible, Target>;
+    type Screen<Target> = Arc<RwLock<Target>>;
+
+    /// A printer drawing onto `target` by `clock`, moving the text by a pixel every 75ms.
+    fn printer_on<C, Target>(target: Target, clock: &ManualClock) -> (Screen<Target>, TestPrinter<C, Target>)
+        where Target: DrawTarget<Color=C, Error=Infallible> + Send + Sync + 'static, C: PixelColor + Send + 'static {
+        let screen = Arc::new(RwLock::new(target));
+        let printer = LedPrinter::with_clock(Arc::clone(&screen), 75, Arc::new(clock.clone())).unwrap();
+        (screen, printer)
+    }
+
+    /// A printer on a recorded screen `width` pixels wide and 5 high, by a manual clock.
+    fn headless<C>(width: u32, background: C) -> (Screen<Recorder<C>>, ManualClock, TestPrinter<C, Recorder<C>>)
+        where C: PixelColor + Send + Sync + 'static {
+        let clock = ManualClock::new();
+        let (screen, printer) = printer_on(RecordingTarget::headless(Size::new(width, 5), background), &clock);
+        (screen, clock, printer)
+    }
+
+    /// Lets `pixels` scroll steps happen one at a time, so each of them is recorded as a frame.
+    fn step<C, Target>(printer: &mut TestPrinter<C, Target>, clock: &ManualClock, pixels: usize)
+        where Target: DrawTarget<Color=C, Error=Infallible> + Send + Sync + 'static, C: PixelColor + Send + 'static {
+        for _ in 0..pixels {
+            clock.advance(Duration::from_millis(75));
+            printer.sync().unwrap();
+        }
+    }
+
+    /// A font of embedded-graphics, so the golden files do not depend on the u8g2 font data.
+    fn golden_font() -> Font {
+        Font::mono(&embedded_graphics::mono_font::ascii::FONT_4X6)
+    }
+
+    fn golden(name: &str) -> String {
+        format!("{}/tests/golden/{name}.txt", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    #[test]
+    fn manual_clock_steps_frames() {
+        let (screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.display("Hi", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 0);
+
+        clock.advance(Duration::from_millis(150));
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 2);
+        let screen = screen.read().unwrap();
+        let (first, last) = (&screen.frames()[0], screen.frames().last().unwrap());
+        for point in (0..6).flat_map(|x| (0..5).map(move |y| Point::new(x, y))) {
+            assert_eq!(last.pixel(point), first.pixel(point + Point::new(2, 0)));
+        }
+    }
+
+    #[test]
+    fn manual_clock_times_dwell() {
+        let (_screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        let dwell = Dwell::Duration(Duration::from_secs(2));
+        printer.add_message(Message::new("A", BinaryColor::On, BinaryColor::Off).with_dwell(dwell)).unwrap();
+        printer.add_message(Message::new("B", BinaryColor::On, BinaryColor::Off).with_dwell(dwell)).unwrap();
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "A");
+
+        clock.advance(Duration::from_millis(1999));
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "A");
+        clock.advance(Duration::from_millis(1));
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "B");
+    }
+
+    #[test]
+    fn time_to_live_counts_by_the_printer_clock() {
+        let (_screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        // Far ahead of the wall clock, so an expiry taken from the wall clock would have passed already.
+        clock.advance(Duration::from_secs(3600));
+        printer.set_idle_message(Some(Message::new("B", BinaryColor::On, BinaryColor::Off))).unwrap();
+        printer.display_message(Message::new("A", BinaryColor::On, BinaryColor::Off).with_time_to_live(Duration::from_secs(2))).unwrap();
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "A");
+
+        clock.advance(Duration::from_millis(1999));
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "A");
+        clock.advance(Duration::from_millis(1));
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "B");
+    }
+
+    #[test]
+    fn interrupted_messages_resume_where_they_left_off() {
+        let (screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.display("Hello World", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 5);
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 5);
+        let left_off = screen.read().unwrap().frames().last().cloned();
+
+        let alert = Message::new("!", BinaryColor::On, BinaryColor::Off).with_priority(Priority::High).with_dwell(Dwell::Duration(Duration::from_secs(1)));
+        printer.interrupt(alert).unwrap();
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "!");
+
+        clock.advance(Duration::from_secs(1));
+        printer.sync().unwrap();
+        let showing = printer.status().unwrap().showing.unwrap();
+        assert_eq!((showing.text.as_str(), showing.x_pos), ("Hello World", 5));
+        assert_eq!(screen.read().unwrap().frames().last().cloned(), left_off);
+    }
+
+    #[test]
+    fn messages_replaced_during_an_interrupt_are_not_resumed() {
+        let (_screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.display("Hello World", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        let alert = Message::new("!", BinaryColor::On, BinaryColor::Off).with_priority(Priority::High).with_dwell(Dwell::Duration(Duration::from_secs(1)));
+        printer.interrupt(alert).unwrap();
+        printer.sync().unwrap();
+        printer.display("REEEE", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "!");
+
+        clock.advance(Duration::from_secs(1));
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "REEEE");
+    }
+
     #[test]
-    fn it_works() {
-        let screen = Arc::new(RwLock::new(embedded_graphics_simulator::SimulatorDisplay::<Rgb888>::new(Size::new(5, 5))));
-        let mut printer = LedPrinter::new(Arc::clone(&screen), 75).unwrap();
-        let output_settings = OutputSettingsBuilder::new().scale(20).build();
-
-        let mut window = Window::new("Preview", &output_settings);
-
-        printer.display("Hello, World!", Rgb888::new(255, 255, 255), Rgb888::new(0, 0, 0));
-        let mut running = true;
-        while running {
-            window.update(screen.read().unwrap().deref());
-            for e in window.events() {
-                match e {
-                    SimulatorEvent::Quit => running = false,
-                    SimulatorEvent::KeyDown {
-                        keycode, keymod, repeat
-                    } => {
-                        let to_show = match keycode {
-                            Keycode::W => "REEEE",
-                            Keycode::A => "WOOOOW",
-                            _ => "Hello, World!"
-                        };
-                        println!("Attempting to display {to_show}");
-                        printer.display(to_show, Rgb888::new(255, 255, 255), Rgb888::new(0, 0, 0))
-                    }
-                    _ => {}
-                }
+    fn speed_changes_keep_the_scroll_position() {
+        let (_screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.display("Hello World", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 3);
+        printer.set_scroll_speed(150);
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 3);
+        clock.advance(Duration::from_millis(149));
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 3);
+        clock.advance(Duration::from_millis(1));
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 4);
+    }
+
+    #[test]
+    fn font_changes_lay_out_suspended_messages_again() {
+        let (_screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.set_font(golden_font()).unwrap();
+        printer.set_static_when_fits(Some(Alignment::Left));
+        printer.display("Hi", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.interrupt(Message::new("!", BinaryColor::On, BinaryColor::Off).with_priority(Priority::High).with_dwell(Dwell::Duration(Duration::from_secs(1)))).unwrap();
+        printer.sync().unwrap();
+        // Too wide for the screen in the larger font, so the message has to scroll once it resumes.
+        printer.set_font(Font::mono(&embedded_graphics::mono_font::ascii::FONT_6X10)).unwrap();
+        printer.sync().unwrap();
+        clock.advance(Duration::from_secs(1));
+        printer.sync().unwrap();
+        let showing = printer.status().unwrap().showing.unwrap();
+        assert_eq!((showing.text.as_str(), showing.direction), ("Hi", Some(ScrollDirection::Left)));
+    }
+
+    /// A screen whose every draw fails, so the display task dies on its first frame.
+    #[derive(Debug)]
+    struct BrokenScreen;
+
+    impl OriginDimensions for BrokenScreen {
+        fn size(&self) -> Size {
+            Size::new(8, 5)
+        }
+    }
+
+    impl DrawTarget for BrokenScreen {
+        type Color = BinaryColor;
+        type Error = std::fmt::Error;
+
+        fn draw_iter<I>(&mut self, _pixels: I) -> Result<(), Self::Error> where I: IntoIterator<Item=Pixel<Self::Color>> {
+            Err(std::fmt::Error)
+        }
+    }
+
+    #[test]
+    fn missing_glyphs_are_rejected_up_front() {
+        let (_screen, _clock, mut printer) = headless(8, BinaryColor::Off);
+        assert!(matches!(printer.display("café", BinaryColor::On, BinaryColor::Off), Err(PrinterError::GlyphNotFound('é'))));
+        assert!(!printer.is_running());
+    }
+
+    #[test]
+    fn fonts_missing_glyphs_of_queued_messages_are_rejected() {
+        let (_screen, _clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.set_font(golden_font()).unwrap();
+        printer.display("café", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+
+        assert!(matches!(printer.set_font(Font::default()), Err(PrinterError::GlyphNotFound('é'))));
+        assert!(matches!(printer.set_font(Font::default().with_fallback_glyph(Some('ü'))), Err(PrinterError::GlyphNotFound('ü'))));
+        printer.sync().unwrap();
+        assert!(printer.is_running());
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "café");
+
+        printer.set_font(Font::default().with_fallback_glyph(Some('?'))).unwrap();
+        printer.sync().unwrap();
+        assert!(printer.is_running());
+    }
+
+    #[test]
+    fn worker_errors_show_in_the_status_until_taken() {
+        let clock = ManualClock::new();
+        let mut printer = LedPrinter::with_clock(Arc::new(RwLock::new(BrokenScreen)), 75, Arc::new(clock)).unwrap();
+        // Paused, the display task starts without drawing, so it dies only once resumed.
+        printer.pause().unwrap();
+        printer.display("Hi", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.resume().unwrap();
+        assert!(matches!(printer.sync(), Err(PrinterError::DrawTarget(std::fmt::Error))));
+        assert!(printer.take_error().is_none());
+
+        printer.pause().unwrap();
+        printer.display("Hi", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.resume().unwrap();
+        while printer.is_running() {
+            std::thread::yield_now();
+        }
+        let status = printer.status().unwrap();
+        assert!(!status.running);
+        assert_eq!(status.error, Some(PrinterError::<std::fmt::Error>::DrawTarget(std::fmt::Error).to_string()));
+        assert!(matches!(printer.take_error(), Some(PrinterError::DrawTarget(std::fmt::Error))));
+        assert_eq!(printer.status().unwrap().error, None);
+        assert!(printer.take_error().is_none());
+    }
+
+    #[test]
+    fn hello_world_scrolls_one_pixel_per_frame() {
+        let (screen, clock, mut printer) = headless(5, BinaryColor::Off);
+        printer.set_font(golden_font()).unwrap();
+        printer.display("Hello, World!", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 8);
+
+        let frames = screen.write().unwrap().take_frames();
+        assert_eq!(frames.len(), 9);
+        for (before, after) in frames.iter().zip(&frames[1..]) {
+            for point in (0..4).flat_map(|x| (0..5).map(move |y| Point::new(x, y))) {
+                assert_eq!(after.pixel(point), before.pixel(point + Point::new(1, 0)));
             }
-            sleep(Duration::from_millis(16));
         }
+        assert_golden(&frames, BinaryColor::Off, golden("hello_world_bounce"));
+    }
+
+    #[test]
+    fn display_replaces_the_message_on_screen() {
+        let (screen, clock, mut printer) = headless(5, BinaryColor::Off);
+        printer.set_font(golden_font()).unwrap();
+        printer.display("Hello, World!", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 3);
+        printer.display("REEEE", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 2);
+        let frames = screen.write().unwrap().take_frames();
+
+        let (fresh_screen, fresh_clock, mut fresh) = headless(5, BinaryColor::Off);
+        fresh.set_font(golden_font()).unwrap();
+        fresh.display("REEEE", BinaryColor::On, BinaryColor::Off).unwrap();
+        fresh.sync().unwrap();
+        step(&mut fresh, &fresh_clock, 2);
+        let fresh_frames = fresh_screen.write().unwrap().take_frames();
+
+        assert_eq!(frames.len(), 7);
+        assert_eq!(frames[4..], fresh_frames[..]);
+        assert_golden(&frames, BinaryColor::Off, golden("display_replaces"));
+    }
+
+    #[test]
+    fn pause_freezes_the_text_until_resumed() {
+        let (screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.display("Hello", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 2);
+        printer.pause().unwrap();
+        step(&mut printer, &clock, 4);
+        assert!(printer.status().unwrap().paused);
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 2);
+        assert_eq!(screen.read().unwrap().frames().len(), 3);
+
+        printer.resume().unwrap();
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 2);
+        step(&mut printer, &clock, 1);
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 3);
+    }
+
+    #[test]
+    fn stop_keeps_the_playlist_for_resume() {
+        let (screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.display("Hello", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        printer.stop().unwrap();
+        assert!(!printer.is_running());
+        step(&mut printer, &clock, 2);
+        assert_eq!(screen.read().unwrap().frames().len(), 1);
+        assert_eq!(printer.status().unwrap().playlist_len, 1);
+
+        printer.resume().unwrap();
+        printer.sync().unwrap();
+        assert!(printer.is_running());
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "Hello");
+    }
+
+    #[test]
+    fn clear_forgets_everything_and_fills_the_screen() {
+        let (screen, _clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.display("Hello", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.add_message(Message::new("World", BinaryColor::On, BinaryColor::Off)).unwrap();
+        printer.sync().unwrap();
+        printer.interrupt(Message::new("!", BinaryColor::On, BinaryColor::Off).with_priority(Priority::Low)).unwrap();
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().pending_interrupts, 1);
+
+        printer.clear(BinaryColor::On).unwrap();
+        let status = printer.status().unwrap();
+        assert!(!status.running);
+        assert_eq!((status.showing, status.playlist_len, status.pending_interrupts), (None, 0, 0));
+        assert_eq!(screen.read().unwrap().inner().to_grid(BinaryColor::Off), ["########"; 5].join("\n"));
+    }
+
+    #[test]
+    fn dropping_the_printer_joins_the_display_task() {
+        let (screen, _clock, mut printer) = headless(8, BinaryColor::Off);
+        printer.display("Hello", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        assert!(Arc::strong_count(&screen) > 2);
+        drop(printer);
+        assert_eq!(Arc::strong_count(&screen), 1);
+    }
+
+    #[test]
+    fn markup_colors_each_segment() {
+        let (screen, _clock, mut printer) = headless(16, Rgb888::BLACK);
+        printer.set_static_when_fits(Some(Alignment::Left));
+        printer.display_markup("[red]II[/]II", Rgb888::WHITE, Rgb888::BLACK).unwrap();
+        printer.sync().unwrap();
+
+        let frame = screen.read().unwrap().frames().last().unwrap().clone();
+        let columns_in = |color: Rgb888| (0..16).filter(|&x| (0..5).any(|y| frame.pixel(Point::new(x, y)) == Some(color))).collect::<Vec<_>>();
+        let (red, white) = (columns_in(Rgb888::RED), columns_in(Rgb888::WHITE));
+        assert!(!red.is_empty() && !white.is_empty());
+        assert!(red.iter().max() < white.iter().min());
+        assert!(matches!(printer.display_markup("[red]oops", Rgb888::WHITE, Rgb888::BLACK), Ok(())));
+        assert!(matches!(printer.display_markup("[/]", Rgb888::WHITE, Rgb888::BLACK), Err(PrinterError::Markup(MarkupError::UnmatchedClose(0)))));
+    }
+
+    #[test]
+    fn animated_effects_redraw_static_text() {
+        let (screen, clock, mut printer) = headless(16, Rgb888::BLACK);
+        printer.set_static_when_fits(Some(Alignment::Left));
+        printer.display_with_effect("II", ColorEffect::Gradient { from: Rgb888::RED, to: Rgb888::BLUE }, Rgb888::BLACK).unwrap();
+        printer.sync().unwrap();
+        clock.advance(Duration::from_millis(150));
+        printer.sync().unwrap();
+        assert_eq!(screen.write().unwrap().take_frames().len(), 1);
+
+        let rainbow = ColorEffect::Rainbow { spread: 8, cycle: Duration::from_millis(600) };
+        printer.display_with_effect("II", rainbow, Rgb888::BLACK).unwrap();
+        printer.sync().unwrap();
+        clock.advance(Duration::from_millis(150));
+        printer.sync().unwrap();
+        let frames = screen.write().unwrap().take_frames();
+        assert_eq!(frames.len(), 2);
+        assert_ne!(frames[0], frames[1]);
+    }
+
+    #[test]
+    fn brightness_redraws_without_restarting() {
+        let clock = ManualClock::new();
+        let (screen, mut printer) = printer_on(GammaTarget::new(RecordingTarget::headless(Size::new(5, 5), Rgb888::BLACK)), &clock);
+        printer.display("Hello, World!", Rgb888::WHITE, Rgb888::BLACK).unwrap();
+        printer.sync().unwrap();
+        clock.advance(Duration::from_millis(150));
+        printer.sync().unwrap();
+        printer.set_brightness(0).unwrap();
+        printer.sync().unwrap();
+
+        assert_eq!(printer.brightness().unwrap(), 0);
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 2);
+        let frames = screen.write().unwrap().inner_mut().take_frames();
+        assert_eq!(frames.len(), 3);
+        assert!(frames[1].to_grid(Rgb888::BLACK).contains('#'));
+        assert!(!frames[2].to_grid(Rgb888::BLACK).contains('#'));
+    }
+
+    #[test]
+    fn status_reports_the_current_estimate() {
+        let limiter = CurrentLimiter::new(RecordingTarget::headless(Size::new(5, 5), Rgb888::BLACK), 100)
+            .with_model(CurrentModel { ma_per_channel: 20.0, idle_ma_per_led: 0.0 });
+        let meter = limiter.meter();
+        let (_screen, mut printer) = printer_on(limiter, &ManualClock::new());
+        assert_eq!(printer.status().unwrap().current, None);
+        printer.set_current_meter(Some(meter));
+        printer.display("Hello, World!", Rgb888::WHITE, Rgb888::BLACK).unwrap();
+        printer.sync().unwrap();
+
+        let current = printer.status().unwrap().current.unwrap();
+        assert!(current.is_limited());
+        assert!(current.drawn_ma <= 100);
+    }
+
+    #[test]
+    fn quiet_hours_blank_all_but_critical_messages() {
+        // Monday 2024-01-01 at 23:00 UTC.
+        let clock = ManualClock::starting_at(Instant::now(), UNIX_EPOCH + Duration::from_secs(1_704_150_000));
+        let (screen, mut printer) = printer_on(RecordingTarget::headless(Size::new(8, 5), BinaryColor::Off), &clock);
+        printer.set_schedule(Some(Schedule::new(0)
+            .with_rule(&Weekday::ALL, (22, 0), (7, 0), ScheduledLevel::Blank)
+            .with_override(Some(Priority::Critical))));
+        printer.display("Hi", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().scheduled, Some(ScheduledLevel::Blank));
+        assert!(!screen.read().unwrap().frames().last().unwrap().to_grid(BinaryColor::Off).contains('#'));
+
+        printer.interrupt(Message::new("!", BinaryColor::On, BinaryColor::Off).with_priority(Priority::Critical).with_dwell(Dwell::Duration(Duration::from_secs(1)))).unwrap();
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().scheduled, None);
+        assert!(screen.read().unwrap().frames().last().unwrap().to_grid(BinaryColor::Off).contains('#'));
+
+        clock.advance(Duration::from_secs(2));
+        printer.sync().unwrap();
+        assert_eq!(printer.status().unwrap().showing.unwrap().text, "Hi");
+        assert!(!screen.read().unwrap().frames().last().unwrap().to_grid(BinaryColor::Off).contains('#'));
+    }
+
+    #[test]
+    fn blinks_before_scrolling() {
+        let (screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        let alert = Message::new("Hi", BinaryColor::On, BinaryColor::Off)
+            .with_attention(AttentionEffect::Blink, Duration::from_millis(150), Some(2))
+            .with_dwell(Dwell::Forever);
+        printer.display_message(alert).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 3);
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 0);
+        step(&mut printer, &clock, 1);
+        assert_eq!(printer.status().unwrap().showing.unwrap().x_pos, 1);
+
+        let frames = screen.write().unwrap().take_frames();
+        let lit: Vec<bool> = frames.iter().map(|frame| frame.to_grid(BinaryColor::Off).contains('#')).collect();
+        assert_eq!(lit, [true, false, true, false, true]);
+        assert_eq!(frames[0], frames[2]);
+    }
+
+    #[test]
+    fn invert_swaps_text_and_background() {
+        let (screen, clock, mut printer) = headless(8, BinaryColor::Off);
+        let alert = Message::new("Hi", BinaryColor::On, BinaryColor::Off)
+            .with_attention(AttentionEffect::Invert, Duration::from_millis(150), Some(1));
+        printer.display_message(alert).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 1);
+
+        let frames = screen.write().unwrap().take_frames();
+        assert_eq!(frames.len(), 2);
+        for y in 0..5 {
+            for x in 0..8 {
+                let point = Point::new(x, y);
+                assert_eq!(frames[1].pixel(point), frames[0].pixel(point).map(BinaryColor::invert));
+            }
+        }
+    }
+
+    #[test]
+    fn transition_runs_between_messages() {
+        let (screen, clock, mut printer) = headless(8, Rgb888::BLACK);
+        printer.set_scroll_mode(ScrollMode::Static(Alignment::Left));
+        printer.display("A", Rgb888::WHITE, Rgb888::BLACK).unwrap();
+        printer.sync().unwrap();
+        let next = Message::new("B", Rgb888::WHITE, Rgb888::BLACK)
+            .with_transition(Transition::Push(ScrollDirection::Left), Duration::from_millis(300))
+            .with_dwell(Dwell::Forever);
+        printer.display_message(next).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 4);
+
+        let frames = screen.write().unwrap().take_frames();
+        assert_eq!(frames.len(), 6);
+        // The first frame of the transition still shows the old message, the last one only the new message.
+        assert_eq!(frames[1], frames[0]);
+        assert_ne!(frames[2], frames[0]);
+        assert_ne!(frames[2], frames[5]);
+        printer.display("B", Rgb888::WHITE, Rgb888::BLACK).unwrap();
+        printer.sync().unwrap();
+        assert_eq!(screen.read().unwrap().frames().last(), frames.last());
+    }
+
+    #[test]
+    fn blank_text_clears_once() {
+        let (screen, clock, mut printer) = headless(5, BinaryColor::Off);
+        printer.display(" ", BinaryColor::On, BinaryColor::Off).unwrap();
+        printer.sync().unwrap();
+        step(&mut printer, &clock, 3);
+        let frames = screen.read().unwrap().frames().to_vec();
+        assert_eq!(frames_to_grid(&frames, BinaryColor::Off), ".....\n.....\n.....\n.....\n.....\n");
     }
 }
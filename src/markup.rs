@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+
+/// Why a markup string could not be parsed, positions are byte offsets into the markup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupError {
+    /// A tag names neither a known color nor a `#RRGGBB` value.
+    UnknownColor { position: usize, name: String },
+    /// A `[` without its `]`.
+    UnterminatedTag(usize),
+    /// A `[/]` with no color left to close.
+    UnmatchedClose(usize),
+}
+
+impl Display for MarkupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarkupError::UnknownColor { position, name } => write!(f, "unknown color {name:?} at {position}"),
+            MarkupError::UnterminatedTag(position) => write!(f, "tag at {position} is missing its ']'"),
+            MarkupError::UnmatchedClose(position) => write!(f, "[/] at {position} closes nothing"),
+        }
+    }
+}
+
+impl Error for MarkupError {}
+
+fn named_color(name: &str) -> Option<Rgb888> {
+    let color = match name.to_ascii_lowercase().as_str() {
+        "black" => Rgb888::BLACK,
+        "white" => Rgb888::WHITE,
+        "red" => Rgb888::RED,
+        "green" => Rgb888::GREEN,
+        "blue" => Rgb888::BLUE,
+        "yellow" => Rgb888::YELLOW,
+        "cyan" => Rgb888::CYAN,
+        "magenta" => Rgb888::MAGENTA,
+        "orange" => Rgb888::new(255, 128, 0),
+        "purple" => Rgb888::new(128, 0, 255),
+        "pink" => Rgb888::new(255, 96, 160),
+        _ => return None
+    };
+    Some(color)
+}
+
+/// Character ranges of a text, each with the color it is drawn in.
+pub(crate) type Spans<C> = Vec<(Range<usize>, C)>;
+
+/// Ends the run of characters drawn in the current color, before the color changes.
+fn end_run(spans: &mut Spans<Rgb888>, colors: &[Rgb888], run_start: &mut usize, characters: usize) {
+    if let Some(&color) = colors.last() {
+        if *run_start < characters {
+            spans.push((*run_start..characters, color));
+        }
+    }
+    *run_start = characters;
+}
+
+fn parse_color(name: &str) -> Option<Rgb888> {
+    match name.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => u32::from_str_radix(hex, 16).ok().map(|rgb| Rgb888::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)),
+        Some(_) => None,
+        None => named_color(name)
+    }
+}
+
+/// Splits markup like `BUILD [red]FAILED[/]` into its plain text and the colored character ranges of it.
+///
+/// `[color]` colors everything up to the matching `[/]`, tags nest, and `[[` is a literal `[`.
+/// Colors are names like `red` or `#RRGGBB` values. Ranges count characters, not bytes, and never overlap.
+pub(crate) fn parse(markup: &str) -> Result<(String, Spans<Rgb888>), MarkupError> {
+    let mut text = String::new();
+    let mut characters = 0;
+    let mut spans: Spans<Rgb888> = Vec::new();
+    let mut colors: Vec<Rgb888> = Vec::new();
+    let mut run_start = 0;
+    let mut rest = markup;
+    while let Some(open) = rest.find('[') {
+        let position = markup.len() - rest.len() + open;
+        text.push_str(&rest[..open]);
+        characters += rest[..open].chars().count();
+        let after = &rest[open + 1..];
+        if let Some(after) = after.strip_prefix('[') {
+            text.push('[');
+            characters += 1;
+            rest = after;
+            continue;
+        }
+        let close = after.find(']').ok_or(MarkupError::UnterminatedTag(position))?;
+        let tag = &after[..close];
+        end_run(&mut spans, &colors, &mut run_start, characters);
+        if tag == "/" {
+            colors.pop().ok_or(MarkupError::UnmatchedClose(position))?;
+        } else {
+            let color = parse_color(tag.trim()).ok_or_else(|| MarkupError::UnknownColor { position, name: tag.to_string() })?;
+            colors.push(color);
+        }
+        rest = &after[close + 1..];
+    }
+    text.push_str(rest);
+    characters += rest.chars().count();
+    end_run(&mut spans, &colors, &mut run_start, characters);
+    Ok((text, spans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_tags_become_flat_spans() {
+        let (text, spans) = parse("BUILD [red]FAIL[#00ff00]ED[/]![/] [[ok]").unwrap();
+        assert_eq!(text, "BUILD FAILED! [ok]");
+        assert_eq!(spans, vec![(6..10, Rgb888::RED), (10..12, Rgb888::GREEN), (12..13, Rgb888::RED)]);
+    }
+
+    #[test]
+    fn rejects_bad_tags() {
+        assert_eq!(parse("[mauve]x"), Err(MarkupError::UnknownColor { position: 0, name: "mauve".to_string() }));
+        assert_eq!(parse("a[red"), Err(MarkupError::UnterminatedTag(1)));
+        assert_eq!(parse("a[/]"), Err(MarkupError::UnmatchedClose(1)));
+    }
+}
@@ -0,0 +1,144 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+use embedded_graphics::pixelcolor::{PixelColor, Rgb888};
+use crate::effect::{Attention, AttentionEffect, ColorEffect, FromRgb};
+use crate::markup::{self, MarkupError};
+use crate::transition::{mix, Mix, Transition};
+
+/// Identifies a message in the playlist of a [`crate::LedPrinter`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MessageId(pub(crate) u32);
+
+/// How long a message stays on screen before the playlist moves on to the next one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Dwell {
+    /// Show the message for a fixed amount of time.
+    Duration(Duration),
+    /// Show the message until it has scrolled through the given number of passes.
+    Passes(u32),
+    /// Never move on by itself.
+    Forever,
+}
+
+impl Default for Dwell {
+    fn default() -> Self {
+        Dwell::Passes(1)
+    }
+}
+
+/// Importance of a message, a higher priority interrupt pre-empts whatever is on screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
+/// Text to show on the display along with how to show it.
+#[derive(Debug, Clone)]
+pub struct Message<C> where C: PixelColor {
+    pub(crate) text: String,
+    pub(crate) color: C,
+    pub(crate) black: C,
+    /// Character ranges of `text` drawn in another color than `color`, in order and without overlaps.
+    pub(crate) spans: Vec<(Range<usize>, C)>,
+    /// Replaces `color` and `spans` at render time, along with how to get from RGB to `C`.
+    pub(crate) effect: Option<(ColorEffect, FromRgb<C>)>,
+    pub(crate) attention: Option<Attention>,
+    /// How the message replaces the one before it, along with how to blend two colors.
+    pub(crate) transition: Option<(Transition, Duration, Mix<C>)>,
+    pub(crate) dwell: Dwell,
+    pub(crate) priority: Priority,
+    pub(crate) expires_at: Option<Instant>,
+    /// Turned into `expires_at` by the clock of the printer once the message is handed to it.
+    pub(crate) time_to_live: Option<Duration>,
+}
+
+impl<C> Message<C> where C: PixelColor {
+    /// `text` drawn in `color` over a `black` background.
+    pub fn new(text: impl Into<String>, color: C, black: C) -> Self {
+        Message {
+            text: text.into(),
+            color,
+            black,
+            spans: Vec::new(),
+            effect: None,
+            attention: None,
+            transition: None,
+            dwell: Dwell::default(),
+            priority: Priority::default(),
+            expires_at: None,
+            time_to_live: None,
+        }
+    }
+
+    /// Text with inline colors like `BUILD [red]FAILED[/]`, anything outside tags is drawn in `color`.
+    ///
+    /// Tags nest, `[[` is a literal `[`, and colors are names like `red` or `#RRGGBB` values.
+    pub fn markup(markup: &str, color: C, black: C) -> Result<Self, MarkupError> where C: From<Rgb888> {
+        let (text, spans) = markup::parse(markup)?;
+        let mut message = Message::new(text, color, black);
+        message.spans = spans.into_iter().map(|(range, color)| (range, C::from(color))).collect();
+        Ok(message)
+    }
+
+    /// Colors the text by `effect`, in place of `color` and any markup colors.
+    pub fn with_color_effect(mut self, effect: ColorEffect) -> Self where C: From<Rgb888> {
+        self.effect = Some((effect, C::from));
+        self
+    }
+
+    /// Blinks, flashes or inverts the message, for `cycles` of `period` before the text starts moving, or for as long as it is shown.
+    pub fn with_attention(mut self, effect: AttentionEffect, period: Duration, cycles: Option<u32>) -> Self {
+        self.attention = Some(Attention { effect, period, cycles });
+        self
+    }
+
+    /// Brings the message on screen with `transition` taking `duration`, instead of replacing whatever was there at once.
+    pub fn with_transition(mut self, transition: Transition, duration: Duration) -> Self where C: Into<Rgb888> + From<Rgb888> {
+        self.transition = Some((transition, duration, mix::<C>));
+        self
+    }
+
+    pub fn with_dwell(mut self, dwell: Dwell) -> Self {
+        self.dwell = dwell;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Removes the message from the display once `expires_at` has passed.
+    pub fn with_expiry(mut self, expires_at: Instant) -> Self {
+        self.expires_at = Some(expires_at);
+        self.time_to_live = None;
+        self
+    }
+
+    /// Removes the message from the display once `time_to_live` has passed since it was handed to the printer, by the printer's clock.
+    pub fn with_time_to_live(mut self, time_to_live: Duration) -> Self {
+        self.time_to_live = Some(time_to_live);
+        self.expires_at = None;
+        self
+    }
+
+    /// Starts the time to live of the message at `now`, if it has one.
+    pub(crate) fn queued_at(mut self, now: Instant) -> Self {
+        if let Some(time_to_live) = self.time_to_live.take() {
+            self.expires_at = Some(now + time_to_live);
+        }
+        self
+    }
+
+    pub(crate) fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
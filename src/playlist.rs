@@ -0,0 +1,212 @@
+use std::time::Instant;
+use embedded_graphics::pixelcolor::PixelColor;
+use crate::message::{Message, MessageId, Priority};
+
+/// Identifies a specific revision of a playlist entry, changes whenever the entry is replaced.
+pub(crate) type EntryKey = (MessageId, u32);
+
+#[derive(Debug)]
+struct Entry<C> where C: PixelColor {
+    id: MessageId,
+    revision: u32,
+    message: Message<C>,
+}
+
+/// Messages rotated through by the display task, along with interrupts waiting to pre-empt them.
+#[derive(Debug)]
+pub(crate) struct Playlist<C> where C: PixelColor {
+    entries: Vec<Entry<C>>,
+    idle: Option<Entry<C>>,
+    interrupts: Vec<Message<C>>,
+    cursor: usize,
+    next_revision: u32,
+}
+
+impl<C> Playlist<C> where C: PixelColor {
+    pub(crate) fn new() -> Self {
+        Playlist {
+            entries: Vec::new(),
+            idle: None,
+            interrupts: Vec::new(),
+            cursor: 0,
+            next_revision: 0,
+        }
+    }
+
+    fn revision(&mut self) -> u32 {
+        self.next_revision = self.next_revision.wrapping_add(1);
+        self.next_revision
+    }
+
+    pub(crate) fn add(&mut self, message: Message<C>) -> MessageId {
+        let revision = self.revision();
+        let id = MessageId(revision);
+        self.entries.push(Entry { id, revision, message });
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: MessageId) -> Option<Message<C>> {
+        let position = self.entries.iter().position(|entry| entry.id == id)?;
+        let entry = self.entries.remove(position);
+        if position < self.cursor {
+            self.cursor -= 1;
+        }
+        if self.cursor >= self.entries.len() {
+            self.cursor = 0;
+        }
+        Some(entry.message)
+    }
+
+    pub(crate) fn replace(&mut self, id: MessageId, message: Message<C>) -> Option<Message<C>> {
+        let revision = self.revision();
+        let entry = self.entries.iter_mut().find(|entry| entry.id == id)?;
+        entry.revision = revision;
+        Some(std::mem::replace(&mut entry.message, message))
+    }
+
+    /// Sets the message shown while the playlist is empty.
+    pub(crate) fn set_idle(&mut self, message: Option<Message<C>>) {
+        let revision = self.revision();
+        self.idle = message.map(|message| Entry { id: MessageId(revision), revision, message });
+    }
+
+    /// Drops every message whose expiry has passed.
+    pub(crate) fn remove_expired(&mut self, now: Instant) {
+        let expired: Vec<MessageId> = self.entries.iter()
+            .filter(|entry| entry.message.is_expired(now))
+            .map(|entry| entry.id)
+            .collect();
+        for id in expired {
+            self.remove(id);
+        }
+        if self.idle.as_ref().is_some_and(|idle| idle.message.is_expired(now)) {
+            self.idle = None;
+        }
+        self.interrupts.retain(|message| !message.is_expired(now));
+    }
+
+    /// All queued messages: the entries, the idle message and the pending interrupts.
+    pub(crate) fn messages(&self) -> impl Iterator<Item = &Message<C>> {
+        self.entries.iter()
+            .map(|entry| &entry.message)
+            .chain(self.idle.as_ref().map(|idle| &idle.message))
+            .chain(self.interrupts.iter())
+    }
+
+    /// The earliest expiry among all queued messages.
+    pub(crate) fn next_expiry(&self) -> Option<Instant> {
+        self.messages()
+            .filter_map(|message| message.expires_at)
+            .min()
+    }
+
+    pub(crate) fn push_interrupt(&mut self, message: Message<C>) {
+        self.interrupts.push(message);
+    }
+
+    pub(crate) fn clear_interrupts(&mut self) {
+        self.interrupts.clear();
+    }
+
+    pub(crate) fn pending_interrupts(&self) -> usize {
+        self.interrupts.len()
+    }
+
+    /// Takes the oldest of the highest priority interrupts, if it beats `above`.
+    pub(crate) fn take_interrupt(&mut self, above: Option<Priority>) -> Option<Message<C>> {
+        let mut best: Option<(usize, Priority)> = None;
+        for (index, message) in self.interrupts.iter().enumerate() {
+            let beats_best = best.is_none_or(|(_, priority)| message.priority > priority);
+            let beats_above = above.is_none_or(|priority| message.priority > priority);
+            if beats_best && beats_above {
+                best = Some((index, message.priority));
+            }
+        }
+        best.map(|(index, _)| self.interrupts.remove(index))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.cursor = 0;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The message to show, falling back to the idle message while the playlist is empty.
+    pub(crate) fn current(&self) -> Option<(EntryKey, &Message<C>)> {
+        self.entries.get(self.cursor).or(self.idle.as_ref()).map(|entry| ((entry.id, entry.revision), &entry.message))
+    }
+
+    /// Moves on to the next message, wrapping around at the end.
+    pub(crate) fn advance(&mut self) {
+        if !self.entries.is_empty() {
+            self.cursor = (self.cursor + 1) % self.entries.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::BinaryColor;
+    use super::*;
+
+    fn message(text: &str) -> Message<BinaryColor> {
+        Message::new(text, BinaryColor::On, BinaryColor::Off)
+    }
+
+    #[test]
+    fn rotates_and_survives_removal() {
+        let mut playlist = Playlist::new();
+        let build = playlist.add(message("BUILD"));
+        let on_call = playlist.add(message("ON CALL"));
+        let meeting = playlist.add(message("MEETING"));
+        playlist.advance();
+        assert_eq!(playlist.current().unwrap().1.text(), "ON CALL");
+        playlist.remove(build);
+        assert_eq!(playlist.current().unwrap().1.text(), "ON CALL");
+        playlist.remove(on_call);
+        assert_eq!(playlist.current().unwrap().1.text(), "MEETING");
+        playlist.advance();
+        assert_eq!(playlist.current().unwrap().0.0, meeting);
+    }
+
+    #[test]
+    fn replace_changes_the_entry_key() {
+        let mut playlist = Playlist::new();
+        let id = playlist.add(message("BUILD"));
+        let before = playlist.current().unwrap().0;
+        assert_eq!(playlist.replace(id, message("FAILED")).unwrap().text(), "BUILD");
+        let (after, current) = playlist.current().unwrap();
+        assert_ne!(before, after);
+        assert_eq!(current.text(), "FAILED");
+    }
+
+    #[test]
+    fn expired_messages_fall_back_to_idle() {
+        let mut playlist = Playlist::new();
+        let now = Instant::now();
+        playlist.set_idle(Some(message("IDLE")));
+        playlist.add(message("MEETING").with_expiry(now));
+        playlist.add(message("LUNCH").with_expiry(now + std::time::Duration::from_secs(60)));
+        playlist.remove_expired(now);
+        assert_eq!(playlist.current().unwrap().1.text(), "LUNCH");
+        playlist.remove_expired(now + std::time::Duration::from_secs(60));
+        assert_eq!(playlist.current().unwrap().1.text(), "IDLE");
+    }
+
+    #[test]
+    fn interrupts_are_taken_by_priority_then_age() {
+        let mut playlist = Playlist::new();
+        for (text, priority) in [("A", Priority::High), ("B", Priority::Critical), ("C", Priority::Critical), ("D", Priority::Low)] {
+            playlist.push_interrupt(message(text).with_priority(priority));
+        }
+        assert!(playlist.take_interrupt(Some(Priority::Critical)).is_none());
+        assert_eq!(playlist.take_interrupt(Some(Priority::Normal)).unwrap().text(), "B");
+        assert_eq!(playlist.take_interrupt(None).unwrap().text(), "C");
+        assert!(playlist.take_interrupt(Some(Priority::High)).is_none());
+        assert_eq!(playlist.take_interrupt(None).unwrap().text(), "A");
+        assert_eq!(playlist.pending_interrupts(), 1);
+    }
+}
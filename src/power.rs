@@ -0,0 +1,216 @@
+use std::sync::{Arc, Mutex};
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Dimensions, OriginDimensions, Point};
+use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+use crate::record::Frame;
+
+/// How much current an LED draws, by default that of a WS2812B.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CurrentModel {
+    /// Current of one color channel at full value, in mA.
+    pub ma_per_channel: f32,
+    /// Current of an LED that is off, in mA.
+    pub idle_ma_per_led: f32,
+}
+
+impl Default for CurrentModel {
+    fn default() -> Self {
+        CurrentModel {
+            ma_per_channel: 20.0,
+            idle_ma_per_led: 1.0,
+        }
+    }
+}
+
+/// Estimated current of the frame on the LEDs, see [`CurrentLimiter`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CurrentReading {
+    /// What the frame would draw as drawn by the printer, in mA.
+    pub requested_ma: u32,
+    /// What the frame draws after scaling it down to the budget, in mA.
+    pub drawn_ma: u32,
+}
+
+impl CurrentReading {
+    /// Whether the frame had to be dimmed to stay within the budget.
+    pub fn is_limited(&self) -> bool {
+        self.drawn_ma < self.requested_ma
+    }
+}
+
+/// Shared view of the latest [`CurrentReading`] of a [`CurrentLimiter`], see [`crate::LedPrinter::set_current_meter`].
+#[derive(Debug, Clone, Default)]
+pub struct CurrentMeter {
+    reading: Arc<Mutex<Option<CurrentReading>>>,
+}
+
+impl CurrentMeter {
+    /// The latest reading, `None` until something was drawn.
+    pub fn reading(&self) -> Option<CurrentReading> {
+        self.reading.lock().map_or(None, |reading| *reading)
+    }
+
+    fn record(&self, reading: CurrentReading) {
+        if let Ok(mut current) = self.reading.lock() {
+            *current = Some(reading);
+        }
+    }
+}
+
+/// Wraps a draw target, dimming whole frames whenever they would draw more current than `budget_ma`.
+///
+/// The estimate uses the colors reaching the LEDs, so wrap the hardware target directly, inside any [`crate::GammaTarget`].
+#[derive(Debug)]
+pub struct CurrentLimiter<Target> where Target: DrawTarget {
+    target: Target,
+    budget_ma: u32,
+    model: CurrentModel,
+    /// The frame as drawn, before scaling.
+    frame: Frame<Target::Color>,
+    /// Sum of all color channels of `frame`.
+    channel_sum: u64,
+    scale: f32,
+    meter: CurrentMeter,
+}
+
+impl<Target> CurrentLimiter<Target> where Target: DrawTarget, Target::Color: Into<Rgb888> + From<Rgb888> {
+    pub fn new(target: Target, budget_ma: u32) -> Self {
+        let size = target.bounding_box().size;
+        let mut limiter = CurrentLimiter {
+            target,
+            budget_ma,
+            model: CurrentModel::default(),
+            frame: Frame::new(size, Rgb888::BLACK.into()),
+            channel_sum: 0,
+            scale: 1.0,
+            meter: CurrentMeter::default(),
+        };
+        limiter.update_scale();
+        limiter
+    }
+
+    pub fn with_model(mut self, model: CurrentModel) -> Self {
+        self.model = model;
+        self.update_scale();
+        self
+    }
+
+    /// A handle on the estimate of the frame on the LEDs, for [`crate::LedPrinter::set_current_meter`].
+    pub fn meter(&self) -> CurrentMeter {
+        self.meter.clone()
+    }
+
+    pub fn inner(&self) -> &Target {
+        &self.target
+    }
+
+    /// The wrapped target, e.g. to flush it to the LEDs.
+    pub fn inner_mut(&mut self) -> &mut Target {
+        &mut self.target
+    }
+
+    fn channels(color: Target::Color) -> u64 {
+        let color: Rgb888 = color.into();
+        color.r() as u64 + color.g() as u64 + color.b() as u64
+    }
+
+    /// Works out the scale keeping the frame within budget and records the estimate, returns whether the scale changed.
+    fn update_scale(&mut self) -> bool {
+        let size = self.frame.size();
+        let idle = self.model.idle_ma_per_led * (size.width * size.height) as f32;
+        let lit = self.model.ma_per_channel * self.channel_sum as f32 / 255.0;
+        let scale = if lit > 0.0 { ((self.budget_ma as f32 - idle) / lit).clamp(0.0, 1.0) } else { 1.0 };
+        self.meter.record(CurrentReading {
+            requested_ma: (idle + lit).round() as u32,
+            drawn_ma: (idle + lit * scale).round() as u32,
+        });
+        let changed = scale != self.scale;
+        self.scale = scale;
+        changed
+    }
+
+    fn scaled(&self, color: Target::Color) -> Target::Color {
+        if self.scale >= 1.0 {
+            return color;
+        }
+        let color: Rgb888 = color.into();
+        // Rounding down keeps the frame under budget.
+        let channel = |value: u8| (value as f32 * self.scale) as u8;
+        Rgb888::new(channel(color.r()), channel(color.g()), channel(color.b())).into()
+    }
+
+    /// Draws the whole frame again at the current scale.
+    fn redraw(&mut self) -> Result<(), Target::Error> {
+        let size = self.frame.size();
+        let area = Rectangle::new(self.target.bounding_box().top_left, size);
+        let colors: Vec<Target::Color> = (0..size.height as i32)
+            .flat_map(|y| (0..size.width as i32).map(move |x| Point::new(x, y)))
+            .filter_map(|point| self.frame.pixel(point))
+            .map(|color| self.scaled(color))
+            .collect();
+        self.target.fill_contiguous(&area, colors)
+    }
+}
+
+impl<Target> Dimensions for CurrentLimiter<Target> where Target: DrawTarget {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<Target> DrawTarget for CurrentLimiter<Target> where Target: DrawTarget, Target::Color: Into<Rgb888> + From<Rgb888> {
+    type Color = Target::Color;
+    type Error = Target::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error> where I: IntoIterator<Item=Pixel<Self::Color>> {
+        let origin = self.target.bounding_box().top_left;
+        let mut drawn = Vec::new();
+        for Pixel(point, color) in pixels {
+            let Some(previous) = self.frame.pixel(point - origin) else { continue };
+            self.channel_sum = self.channel_sum - Self::channels(previous) + Self::channels(color);
+            let _ = self.frame.draw_iter([Pixel(point - origin, color)]);
+            drawn.push(Pixel(point, color));
+        }
+        if self.update_scale() {
+            return self.redraw();
+        }
+        let scaled: Vec<Pixel<Self::Color>> = drawn.into_iter().map(|Pixel(point, color)| Pixel(point, self.scaled(color))).collect();
+        self.target.draw_iter(scaled)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let _ = self.frame.clear(color);
+        let size = self.frame.size();
+        self.channel_sum = Self::channels(color) * (size.width * size.height) as u64;
+        self.update_scale();
+        let color = self.scaled(color);
+        self.target.clear(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::geometry::Size;
+    use super::*;
+
+    #[test]
+    fn dims_frames_over_budget() {
+        let model = CurrentModel { ma_per_channel: 20.0, idle_ma_per_led: 0.0 };
+        let mut limiter = CurrentLimiter::new(Frame::new(Size::new(2, 1), Rgb888::BLACK), 60).with_model(model);
+        let meter = limiter.meter();
+        limiter.draw_iter([Pixel(Point::new(0, 0), Rgb888::WHITE)]).unwrap();
+        assert_eq!(limiter.inner().pixel(Point::new(0, 0)), Some(Rgb888::WHITE));
+        assert!(!meter.reading().unwrap().is_limited());
+
+        limiter.draw_iter([Pixel(Point::new(1, 0), Rgb888::WHITE)]).unwrap();
+        let half = Rgb888::new(127, 127, 127);
+        assert_eq!(limiter.inner().pixel(Point::new(0, 0)), Some(half));
+        assert_eq!(limiter.inner().pixel(Point::new(1, 0)), Some(half));
+        assert_eq!(meter.reading(), Some(CurrentReading { requested_ma: 120, drawn_ma: 60 }));
+
+        limiter.clear(Rgb888::BLACK).unwrap();
+        assert_eq!(meter.reading(), Some(CurrentReading { requested_ma: 0, drawn_ma: 0 }));
+    }
+}
@@ -0,0 +1,188 @@
+use std::convert::Infallible;
+#[cfg(any(test, feature = "test-support"))]
+use std::path::Path;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Dimensions, OriginDimensions, Point, Size};
+use embedded_graphics::pixelcolor::PixelColor;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+
+/// Environment variable that makes [`assert_golden`] write golden files instead of comparing against them.
+#[cfg(any(test, feature = "test-support"))]
+pub const BLESS_ENV: &str = "LED_PRINTER_BLESS";
+
+/// An in-memory image, usable as a headless draw target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame<C> where C: PixelColor {
+    size: Size,
+    pixels: Vec<C>,
+}
+
+impl<C> Frame<C> where C: PixelColor {
+    pub fn new(size: Size, background: C) -> Self {
+        Frame {
+            size,
+            pixels: vec![background; (size.width * size.height) as usize],
+        }
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        let inside = point.x >= 0 && point.y >= 0 && (point.x as u32) < self.size.width && (point.y as u32) < self.size.height;
+        inside.then(|| point.y as usize * self.size.width as usize + point.x as usize)
+    }
+
+    /// The pixel at `point`, `None` outside the frame.
+    pub fn pixel(&self, point: Point) -> Option<C> {
+        self.index(point).map(|index| self.pixels[index])
+    }
+
+    /// Renders the frame as text, one line per row, `.` for pixels in `black` and `#` for anything else.
+    pub fn to_grid(&self, black: C) -> String {
+        self.pixels.chunks(self.size.width.max(1) as usize)
+            .map(|row| row.iter().map(|&pixel| if pixel == black { '.' } else { '#' }).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl<C> OriginDimensions for Frame<C> where C: PixelColor {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl<C> DrawTarget for Frame<C> where C: PixelColor {
+    type Color = C;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error> where I: IntoIterator<Item=Pixel<Self::Color>> {
+        for Pixel(point, color) in pixels {
+            if let Some(index) = self.index(point) {
+                self.pixels[index] = color;
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.pixels.fill(color);
+        Ok(())
+    }
+}
+
+/// Wraps a draw target and keeps a copy of every frame drawn onto it.
+///
+/// The display task clears the target at the start of every frame, so each `clear` starts a new recorded frame.
+/// Anything drawn before the first `clear` reaches the wrapped target but is not recorded.
+#[derive(Debug)]
+pub struct RecordingTarget<Target> where Target: DrawTarget {
+    target: Target,
+    frames: Vec<Frame<Target::Color>>,
+}
+
+impl<Target> RecordingTarget<Target> where Target: DrawTarget {
+    pub fn new(target: Target) -> Self {
+        RecordingTarget {
+            target,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Every frame recorded so far, the last one is the frame currently on the target.
+    pub fn frames(&self) -> &[Frame<Target::Color>] {
+        &self.frames
+    }
+
+    /// Hands out the recorded frames and starts over with an empty recording.
+    pub fn take_frames(&mut self) -> Vec<Frame<Target::Color>> {
+        std::mem::take(&mut self.frames)
+    }
+
+    pub fn inner(&self) -> &Target {
+        &self.target
+    }
+
+    pub fn into_inner(self) -> Target {
+        self.target
+    }
+}
+
+impl<C> RecordingTarget<Frame<C>> where C: PixelColor {
+    /// A recorder that draws nowhere but into its frames.
+    pub fn headless(size: Size, background: C) -> Self {
+        RecordingTarget::new(Frame::new(size, background))
+    }
+}
+
+impl<Target> Dimensions for RecordingTarget<Target> where Target: DrawTarget {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<Target> DrawTarget for RecordingTarget<Target> where Target: DrawTarget {
+    type Color = Target::Color;
+    type Error = Target::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error> where I: IntoIterator<Item=Pixel<Self::Color>> {
+        let pixels: Vec<Pixel<Self::Color>> = pixels.into_iter().collect();
+        if let Some(frame) = self.frames.last_mut() {
+            let origin = self.target.bounding_box().top_left;
+            let _ = frame.draw_iter(pixels.iter().map(|&Pixel(point, color)| Pixel(point - origin, color)));
+        }
+        self.target.draw_iter(pixels)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.frames.push(Frame::new(self.target.bounding_box().size, color));
+        self.target.clear(color)
+    }
+}
+
+/// Renders `frames` with [`Frame::to_grid`], separated by blank lines.
+pub fn frames_to_grid<C>(frames: &[Frame<C>], black: C) -> String where C: PixelColor {
+    let mut grid = frames.iter().map(|frame| frame.to_grid(black)).collect::<Vec<_>>().join("\n\n");
+    grid.push('\n');
+    grid
+}
+
+/// Compares `frames` against the golden file at `path`, in the format of [`frames_to_grid`].
+///
+/// While [`BLESS_ENV`] is set the golden file is written instead, a missing golden file fails otherwise.
+#[cfg(any(test, feature = "test-support"))]
+pub fn assert_golden<C>(frames: &[Frame<C>], black: C, path: impl AsRef<Path>) where C: PixelColor {
+    let path = path.as_ref();
+    let actual = frames_to_grid(frames, black);
+    if std::env::var_os(BLESS_ENV).is_some() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap_or_else(|error| panic!("cannot create {}: {error}", parent.display()));
+        }
+        std::fs::write(path, &actual).unwrap_or_else(|error| panic!("cannot write {}: {error}", path.display()));
+        return;
+    }
+    let expected = std::fs::read_to_string(path)
+        .unwrap_or_else(|error| panic!("cannot read {}: {error}, run with {BLESS_ENV}=1 to create it", path.display()));
+    assert!(
+        expected.replace("\r\n", "\n") == actual,
+        "frames differ from {}, rerun with {BLESS_ENV}=1 to accept them\n--- expected\n{expected}\n--- actual\n{actual}",
+        path.display()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::BinaryColor;
+    use super::*;
+
+    #[test]
+    fn every_clear_starts_a_frame() {
+        let mut target = RecordingTarget::headless(Size::new(3, 2), BinaryColor::Off);
+        target.draw_iter([Pixel(Point::new(0, 0), BinaryColor::On)]).unwrap();
+        assert!(target.frames().is_empty());
+        target.clear(BinaryColor::Off).unwrap();
+        target.draw_iter([Pixel(Point::new(1, 0), BinaryColor::On), Pixel(Point::new(5, 5), BinaryColor::On)]).unwrap();
+        target.clear(BinaryColor::On).unwrap();
+        assert_eq!(frames_to_grid(target.frames(), BinaryColor::Off), ".#.\n...\n\n###\n###\n");
+        assert_eq!(target.inner().to_grid(BinaryColor::Off), "###\n###");
+    }
+}
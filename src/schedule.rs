@@ -0,0 +1,156 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::gamma::Brightness;
+use crate::message::Priority;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub const WEEKDAYS: [Weekday; 5] = [Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday, Weekday::Friday];
+    pub const WEEKEND: [Weekday; 2] = [Weekday::Saturday, Weekday::Sunday];
+    pub const ALL: [Weekday; 7] = [Weekday::Monday, Weekday::Tuesday, Weekday::Wednesday, Weekday::Thursday, Weekday::Friday, Weekday::Saturday, Weekday::Sunday];
+
+    fn from_index(index: u64) -> Self {
+        Weekday::ALL[(index % 7) as usize]
+    }
+
+    fn previous(self) -> Self {
+        Weekday::from_index(self as u64 + 6)
+    }
+}
+
+/// What the display does while a rule of a [`Schedule`] applies.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScheduledLevel {
+    /// Dim to the given share of the brightness, 0 to 255, needs [`Schedule::with_brightness`].
+    Brightness(u8),
+    /// Keep the screen cleared with the background of the message, as [`crate::LedPrinter::clear`] would.
+    Blank,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    days: Vec<Weekday>,
+    /// Minutes since midnight, `end` before `start` runs past midnight into the next day.
+    start: u16,
+    end: u16,
+    level: ScheduledLevel,
+}
+
+impl Rule {
+    fn applies(&self, day: Weekday, minute: u16) -> bool {
+        if self.start <= self.end {
+            self.days.contains(&day) && minute >= self.start && minute < self.end
+        } else {
+            (self.days.contains(&day) && minute >= self.start) || (self.days.contains(&day.previous()) && minute < self.end)
+        }
+    }
+}
+
+/// Times of the week at which the display dims or blanks, evaluated by the display task against its clock.
+///
+/// The first matching rule wins, outside every rule the display runs normally.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    rules: Vec<Rule>,
+    utc_offset: i32,
+    override_priority: Option<Priority>,
+    brightness: Option<Brightness>,
+}
+
+impl Schedule {
+    /// An empty schedule for a place `utc_offset_minutes` ahead of UTC.
+    pub fn new(utc_offset_minutes: i32) -> Self {
+        Schedule {
+            rules: Vec::new(),
+            utc_offset: utc_offset_minutes,
+            override_priority: Some(Priority::High),
+            brightness: None,
+        }
+    }
+
+    /// Applies `level` on `days` from `start` to `end`, as `(hour, minute)`, an `end` before `start` runs into the next day.
+    ///
+    /// E.g. quiet nights are `with_rule(&Weekday::ALL, (22, 0), (7, 0), ScheduledLevel::Blank)`.
+    pub fn with_rule(mut self, days: &[Weekday], start: (u16, u16), end: (u16, u16), level: ScheduledLevel) -> Self {
+        let minutes = |(hour, minute): (u16, u16)| (hour * 60 + minute).min(MINUTES_PER_DAY);
+        self.rules.push(Rule { days: days.to_vec(), start: minutes(start), end: minutes(end), level });
+        self
+    }
+
+    /// Messages of at least `priority` are shown normally whatever the schedule says, `None` to never override.
+    ///
+    /// Defaults to [`Priority::High`].
+    pub fn with_override(mut self, priority: Option<Priority>) -> Self {
+        self.override_priority = priority;
+        self
+    }
+
+    /// The brightness dimmed by [`ScheduledLevel::Brightness`] rules, see [`crate::GammaTarget::brightness_control`].
+    pub fn with_brightness(mut self, brightness: Brightness) -> Self {
+        self.brightness = Some(brightness);
+        self
+    }
+
+    pub(crate) fn brightness(&self) -> Option<&Brightness> {
+        self.brightness.as_ref()
+    }
+
+    pub(crate) fn overrides(&self, priority: Priority) -> bool {
+        self.override_priority.is_some_and(|override_priority| priority >= override_priority)
+    }
+
+    fn local_minutes(&self, time: SystemTime) -> u64 {
+        let minutes = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_secs() as i64 / 60;
+        (minutes + self.utc_offset as i64).max(0) as u64
+    }
+
+    /// The level that applies at `time`, `None` outside every rule.
+    pub fn level_at(&self, time: SystemTime) -> Option<ScheduledLevel> {
+        let minutes = self.local_minutes(time);
+        // 1970-01-01 was a Thursday.
+        let day = Weekday::from_index(minutes / MINUTES_PER_DAY as u64 + Weekday::Thursday as u64);
+        let minute = (minutes % MINUTES_PER_DAY as u64) as u16;
+        self.rules.iter().find(|rule| rule.applies(day, minute)).map(|rule| rule.level)
+    }
+
+    /// How long until the next minute starts, when a rule may start or end.
+    pub(crate) fn until_next_minute(&self, time: SystemTime) -> Duration {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+        Duration::from_secs(60 - since_epoch.as_secs() % 60) - Duration::from_nanos(since_epoch.subsec_nanos() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Monday 2024-01-01 at `hour`:`minute` UTC.
+    fn monday(hour: u64, minute: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_704_067_200 + hour * 3600 + minute * 60)
+    }
+
+    #[test]
+    fn rules_cover_nights_and_weekends() {
+        let schedule = Schedule::new(60)
+            .with_rule(&Weekday::WEEKEND, (0, 0), (24, 0), ScheduledLevel::Blank)
+            .with_rule(&Weekday::ALL, (22, 0), (7, 0), ScheduledLevel::Brightness(40));
+        // 05:30 UTC is 06:30 local, still in the night that started on Sunday.
+        assert_eq!(schedule.level_at(monday(5, 30)), Some(ScheduledLevel::Brightness(40)));
+        assert_eq!(schedule.level_at(monday(6, 0)), None);
+        assert_eq!(schedule.level_at(monday(21, 0)), Some(ScheduledLevel::Brightness(40)));
+        // Saturday, five days on.
+        assert_eq!(schedule.level_at(monday(12 + 5 * 24, 0)), Some(ScheduledLevel::Blank));
+        assert_eq!(schedule.until_next_minute(monday(1, 0) + Duration::from_millis(59_500)), Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,344 @@
+use std::time::{Duration, Instant};
+use embedded_graphics::text::Alignment;
+
+/// How a message moves across the draw target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ScrollMode {
+    /// Scroll back and forth between the start and the end of the text.
+    #[default]
+    Bounce,
+    /// Continuously loop the text from right to left, repeating it after `gap` blank pixels.
+    Marquee { gap: u32 },
+    /// Enter from the right edge, leave past the left edge, then start over.
+    RightToLeft,
+    /// Enter from the left edge, leave past the right edge, then start over.
+    LeftToRight,
+    /// Draw the text once, aligned within the target, and stop redrawing.
+    Static(Alignment),
+}
+
+/// Direction text travels across the screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Left,
+    Right
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Direction {
+    Left,
+    Right
+}
+
+/// Tracks the horizontal position of a message for a given [`ScrollMode`].
+///
+/// `x_pos` is the number of pixels the text is shifted to the left, so the text is drawn at `-x_pos`.
+#[derive(Debug, Clone)]
+pub(crate) struct Scroller {
+    mode: ScrollMode,
+    text_width: i32,
+    view_width: i32,
+    x_pos: i32,
+    direction: Direction,
+    passes: u32,
+    static_steps: i32,
+}
+
+impl Scroller {
+    pub(crate) fn new(mode: ScrollMode, text_width: i32, view_width: i32) -> Self {
+        let x_pos = match mode {
+            ScrollMode::Bounce => 0,
+            ScrollMode::Marquee { .. } | ScrollMode::RightToLeft => -view_width,
+            ScrollMode::LeftToRight => text_width,
+            ScrollMode::Static(Alignment::Left) => 0,
+            ScrollMode::Static(Alignment::Center) => (text_width - view_width) / 2,
+            ScrollMode::Static(Alignment::Right) => text_width - view_width,
+        };
+        Scroller {
+            mode,
+            text_width,
+            view_width,
+            x_pos,
+            direction: Direction::Right,
+            passes: 0,
+            static_steps: 0,
+        }
+    }
+
+    /// Number of times the text went all the way through its scroll pattern.
+    ///
+    /// Static text completes a pass in the time it would take to scroll across the target.
+    pub(crate) fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    /// Number of pixels the text is shifted to the left.
+    pub(crate) fn x_pos(&self) -> i32 {
+        self.x_pos
+    }
+
+    /// Direction the text is currently moving in, `None` if it never moves.
+    pub(crate) fn direction(&self) -> Option<ScrollDirection> {
+        match self.mode {
+            ScrollMode::Bounce => match self.direction {
+                Direction::Left => Some(ScrollDirection::Right),
+                Direction::Right => Some(ScrollDirection::Left)
+            },
+            ScrollMode::Marquee { .. } | ScrollMode::RightToLeft => Some(ScrollDirection::Left),
+            ScrollMode::LeftToRight => Some(ScrollDirection::Right),
+            ScrollMode::Static(_) => None
+        }
+    }
+
+    /// Whether the text never moves, so a single frame is enough to show it.
+    pub(crate) fn is_static(&self) -> bool {
+        matches!(self.mode, ScrollMode::Static(_))
+    }
+
+    /// Distance between two consecutive copies of the text in marquee mode.
+    fn period(&self) -> Option<i32> {
+        match self.mode {
+            ScrollMode::Marquee { gap } => Some((self.text_width + gap as i32).max(1)),
+            _ => None
+        }
+    }
+
+    /// X coordinates at which a copy of the text has to be drawn for the current frame.
+    pub(crate) fn draw_positions(&self) -> impl Iterator<Item=i32> {
+        let start = -self.x_pos;
+        let (count, period) = match self.period() {
+            Some(period) => {
+                let visible = (self.view_width - start).max(0);
+                ((visible + period - 1) / period, period)
+            }
+            None => (1, 0)
+        };
+        (0..count).map(move |i| start + i * period)
+    }
+
+    /// Number of pixels after which the text is back where it was, one pass later.
+    fn cycle(&self) -> u32 {
+        let text_width = self.text_width.max(0) as u32;
+        let view_width = self.view_width.max(0) as u32;
+        match self.mode {
+            ScrollMode::Bounce => 2 * text_width + 2,
+            ScrollMode::Marquee { .. } => self.period().unwrap_or(1) as u32,
+            ScrollMode::RightToLeft | ScrollMode::LeftToRight => text_width + view_width + 1,
+            ScrollMode::Static(_) => view_width.max(1),
+        }
+    }
+
+    /// Advances the text by `pixels`, skipping whole cycles so long gaps take no longer than a single one.
+    pub(crate) fn advance(&mut self, pixels: u32) {
+        // A marquee enters from the right edge once before it starts looping.
+        let lead_in = match self.mode {
+            ScrollMode::Marquee { .. } => (-self.x_pos).max(0) as u32,
+            _ => 0
+        };
+        for _ in 0..lead_in.min(pixels) {
+            self.step();
+        }
+        let pixels = pixels.saturating_sub(lead_in);
+        let cycle = self.cycle();
+        self.passes = self.passes.saturating_add(pixels / cycle);
+        for _ in 0..pixels % cycle {
+            self.step();
+        }
+    }
+
+    /// Advances the text by one pixel.
+    pub(crate) fn step(&mut self) {
+        match self.mode {
+            ScrollMode::Bounce => match self.direction {
+                Direction::Left => {
+                    if self.x_pos == 0 {
+                        self.direction = Direction::Right;
+                        self.passes += 1;
+                    } else {
+                        self.x_pos -= 1;
+                    }
+                },
+                Direction::Right => {
+                    if self.x_pos == self.text_width {
+                        self.direction = Direction::Left;
+                    } else {
+                        self.x_pos += 1;
+                    }
+                }
+            },
+            ScrollMode::Marquee { .. } => {
+                let period = self.period().unwrap_or(1);
+                self.x_pos += 1;
+                if self.x_pos >= period {
+                    self.x_pos -= period;
+                    self.passes += 1;
+                }
+            },
+            ScrollMode::RightToLeft => {
+                if self.x_pos >= self.text_width {
+                    self.x_pos = -self.view_width;
+                    self.passes += 1;
+                } else {
+                    self.x_pos += 1;
+                }
+            },
+            ScrollMode::LeftToRight => {
+                if self.x_pos <= -self.view_width {
+                    self.x_pos = self.text_width;
+                    self.passes += 1;
+                } else {
+                    self.x_pos -= 1;
+                }
+            },
+            ScrollMode::Static(_) => {
+                self.static_steps += 1;
+                if self.static_steps >= self.view_width.max(1) {
+                    self.static_steps = 0;
+                    self.passes += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Turns elapsed time into whole pixels of movement, carrying the remainder over to the next frame.
+///
+/// This keeps the scroll speed exact no matter how late the display task wakes up or how long drawing takes.
+#[derive(Debug, Clone)]
+pub(crate) struct PixelTimer {
+    period: Duration,
+    last: Instant,
+    carry: Duration,
+}
+
+impl PixelTimer {
+    pub(crate) fn new(ms_per_pixel: u16, now: Instant) -> Self {
+        PixelTimer {
+            period: Self::period(ms_per_pixel),
+            last: now,
+            carry: Duration::ZERO,
+        }
+    }
+
+    fn period(ms_per_pixel: u16) -> Duration {
+        Duration::from_millis(ms_per_pixel.max(1) as u64)
+    }
+
+    pub(crate) fn set_ms_per_pixel(&mut self, ms_per_pixel: u16) {
+        self.period = Self::period(ms_per_pixel);
+        self.carry = self.carry.min(self.period);
+    }
+
+    /// Starts counting from `now`, dropping any partial pixel.
+    pub(crate) fn reset(&mut self, now: Instant) {
+        self.last = now;
+        self.carry = Duration::ZERO;
+    }
+
+    /// Number of pixels the text should move by at `now`.
+    pub(crate) fn advance(&mut self, now: Instant) -> u32 {
+        let total = self.carry + now.saturating_duration_since(self.last);
+        self.last = now;
+        let pixels = (total.as_nanos() / self.period.as_nanos()).min(u32::MAX as u128) as u32;
+        // At most a period even when the pixel count was capped, so the next pixel is never due before `last`.
+        self.carry = (total - self.period * pixels).min(self.period);
+        pixels
+    }
+
+    /// When the next whole pixel is due.
+    pub(crate) fn next_pixel_at(&self) -> Instant {
+        self.last + (self.period - self.carry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marquee_enters_from_the_right_and_wraps() {
+        let mut scroller = Scroller::new(ScrollMode::Marquee { gap: 2 }, 4, 5);
+        assert_eq!(scroller.draw_positions().count(), 0);
+        for _ in 0..5 {
+            scroller.step();
+        }
+        assert_eq!(scroller.draw_positions().collect::<Vec<_>>(), vec![0]);
+        for _ in 0..6 {
+            scroller.step();
+        }
+        assert_eq!(scroller.draw_positions().collect::<Vec<_>>(), vec![0]);
+        scroller.step();
+        scroller.step();
+        assert_eq!(scroller.draw_positions().collect::<Vec<_>>(), vec![-2, 4]);
+    }
+
+    #[test]
+    fn right_to_left_restarts_off_screen() {
+        let mut scroller = Scroller::new(ScrollMode::RightToLeft, 3, 5);
+        assert_eq!(scroller.draw_positions().collect::<Vec<_>>(), vec![5]);
+        for _ in 0..8 {
+            scroller.step();
+        }
+        assert_eq!(scroller.draw_positions().collect::<Vec<_>>(), vec![-3]);
+        assert_eq!(scroller.passes(), 0);
+        scroller.step();
+        assert_eq!(scroller.draw_positions().collect::<Vec<_>>(), vec![5]);
+        assert_eq!(scroller.passes(), 1);
+    }
+
+    #[test]
+    fn static_alignment() {
+        let center = Scroller::new(ScrollMode::Static(Alignment::Center), 3, 8);
+        assert_eq!(center.draw_positions().collect::<Vec<_>>(), vec![2]);
+        let mut right = Scroller::new(ScrollMode::Static(Alignment::Right), 3, 8);
+        right.step();
+        assert_eq!(right.draw_positions().collect::<Vec<_>>(), vec![5]);
+    }
+
+    #[test]
+    fn advance_matches_single_steps() {
+        let modes = [
+            ScrollMode::Bounce,
+            ScrollMode::Marquee { gap: 2 },
+            ScrollMode::RightToLeft,
+            ScrollMode::LeftToRight,
+            ScrollMode::Static(Alignment::Left),
+        ];
+        for mode in modes {
+            for pixels in [0, 1, 7, 12, 13, 40, 101] {
+                let mut stepped = Scroller::new(mode, 5, 6);
+                stepped.step();
+                stepped.step();
+                let mut advanced = stepped.clone();
+                for _ in 0..pixels {
+                    stepped.step();
+                }
+                advanced.advance(pixels);
+                assert_eq!((advanced.x_pos(), advanced.direction(), advanced.passes()),
+                           (stepped.x_pos(), stepped.direction(), stepped.passes()), "{mode:?} by {pixels}");
+            }
+        }
+    }
+
+    #[test]
+    fn pixel_timer_carries_partial_pixels() {
+        let start = Instant::now();
+        let mut timer = PixelTimer::new(15, start);
+        assert_eq!(timer.advance(start + Duration::from_millis(10)), 0);
+        assert_eq!(timer.advance(start + Duration::from_millis(20)), 1);
+        assert_eq!(timer.next_pixel_at(), start + Duration::from_millis(30));
+        assert_eq!(timer.advance(start + Duration::from_millis(75)), 4);
+        let mut fast = PixelTimer::new(3, start);
+        assert_eq!(fast.advance(start + Duration::from_millis(10)), 3);
+        assert_eq!(fast.next_pixel_at(), start + Duration::from_millis(12));
+    }
+
+    #[test]
+    fn pixel_timer_caps_long_gaps() {
+        let start = Instant::now();
+        let mut timer = PixelTimer::new(1, start);
+        let later = start + Duration::from_secs(60 * 24 * 3600);
+        assert_eq!(timer.advance(later), u32::MAX);
+        assert_eq!(timer.next_pixel_at(), later);
+    }
+}
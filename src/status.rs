@@ -0,0 +1,42 @@
+use embedded_graphics::pixelcolor::PixelColor;
+use crate::message::{MessageId, Priority};
+use crate::power::CurrentReading;
+use crate::schedule::ScheduledLevel;
+use crate::scroll::ScrollDirection;
+
+/// Snapshot of what a [`crate::LedPrinter`] is doing, see [`crate::LedPrinter::status`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrinterStatus<C> where C: PixelColor {
+    /// Whether the display task is alive.
+    pub running: bool,
+    pub paused: bool,
+    /// The message on screen, `None` while the screen is blank.
+    pub showing: Option<ShowingStatus<C>>,
+    /// The level of the [`crate::Schedule`] in effect, `None` outside its rules or while a message overrides it.
+    pub scheduled: Option<ScheduledLevel>,
+    /// Number of messages in the playlist, not counting the idle message.
+    pub playlist_len: usize,
+    /// Number of interrupts waiting for their turn.
+    pub pending_interrupts: usize,
+    /// Estimated current of the frame on the LEDs, if a meter was given with [`crate::LedPrinter::set_current_meter`].
+    pub current: Option<CurrentReading>,
+    /// Why the display task stopped, until the error is taken with [`crate::LedPrinter::take_error`].
+    pub error: Option<String>,
+}
+
+/// The message on screen as last drawn by the display task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShowingStatus<C> where C: PixelColor {
+    /// Playlist id of the message, `None` for interrupts.
+    pub id: Option<MessageId>,
+    pub text: String,
+    pub color: C,
+    pub black: C,
+    pub priority: Priority,
+    /// Number of pixels the text is shifted to the left.
+    pub x_pos: i32,
+    /// Direction the text is moving in, `None` for static text.
+    pub direction: Option<ScrollDirection>,
+    /// Passes completed since the message came on screen.
+    pub passes: u32,
+}
@@ -0,0 +1,701 @@
+use std::error::Error;
+use std::ops::{DerefMut, Range};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::PixelColor;
+use embedded_graphics::text::Alignment;
+use crate::clock::Clock;
+use crate::effect::{AttentionEffect, Recolor};
+use crate::error::PrinterError;
+use crate::font::Font;
+use crate::message::{Dwell, Message};
+use crate::playlist::{EntryKey, Playlist};
+use crate::record::Frame;
+use crate::schedule::{Schedule, ScheduledLevel};
+use crate::scroll::{PixelTimer, ScrollMode, Scroller};
+use crate::status::ShowingStatus;
+use crate::transition::RunningTransition;
+
+/// Printer settings used by the display task.
+#[derive(Debug, Clone)]
+pub(crate) struct TaskSettings {
+    pub(crate) scroll_spp_ms: u16,
+    pub(crate) scroll_mode: ScrollMode,
+    pub(crate) static_when_fits: Option<Alignment>,
+    pub(crate) font: Font,
+    pub(crate) schedule: Option<Schedule>,
+}
+
+/// Requests sent from the printer to the display task.
+#[derive(Debug)]
+pub(crate) enum Command {
+    /// The settings changed, the message on screen is restarted with them.
+    Settings(TaskSettings),
+    /// The scroll speed changed to the given milliseconds per pixel, the message on screen carries on at the new pace.
+    Speed(u16),
+    /// The playlist or the interrupts changed.
+    Refresh,
+    /// Draw the current frame again, e.g. after the brightness changed, without restarting the message.
+    Redraw,
+    Pause,
+    Resume,
+    Stop,
+    /// Replied to once everything before it, and everything due by the clock, has been drawn.
+    Sync(Sender<()>),
+}
+
+/// What the display task reports back to the printer.
+#[derive(Debug)]
+pub(crate) struct TaskState<C, E> where C: PixelColor {
+    /// Why the display task stopped, if it stopped on an error.
+    pub(crate) error: Option<PrinterError<E>>,
+    /// What the display task last drew.
+    pub(crate) showing: Option<ShowingStatus<C>>,
+    /// The level of the schedule in effect.
+    pub(crate) scheduled: Option<ScheduledLevel>,
+}
+
+impl<C, E> TaskState<C, E> where C: PixelColor {
+    pub(crate) fn new() -> Self {
+        TaskState {
+            error: None,
+            showing: None,
+            scheduled: None,
+        }
+    }
+}
+
+/// Part of a message drawn in one color.
+struct Segment<C> {
+    text: String,
+    /// Where the segment starts, relative to the start of the message.
+    x_offset: i32,
+    color: C,
+}
+
+/// Splits `text` into runs of one color, `spans` being the character ranges not drawn in `color`.
+fn segments<C>(font: &Font, text: &str, spans: &[(Range<usize>, C)], color: C) -> Result<Vec<Segment<C>>, u8g2_fonts::LookupError> where C: PixelColor {
+    let characters: Vec<char> = text.chars().collect();
+    let mut runs = Vec::new();
+    let mut cursor = 0;
+    for (range, span_color) in spans {
+        let range = range.start.min(characters.len())..range.end.min(characters.len());
+        if cursor < range.start {
+            runs.push((cursor..range.start, color));
+        }
+        runs.push((range.clone(), *span_color));
+        cursor = cursor.max(range.end);
+    }
+    if cursor < characters.len() {
+        runs.push((cursor..characters.len(), color));
+    }
+    runs.into_iter()
+        .filter(|(range, _)| !range.is_empty())
+        .map(|(range, color)| {
+            let prefix: String = characters[..range.start].iter().collect();
+            Ok(Segment {
+                text: characters[range].iter().collect(),
+                x_offset: font.advance(&prefix)?,
+                color,
+            })
+        })
+        .collect()
+}
+
+/// A message on screen, or suspended by an interrupt.
+struct Showing<C> where C: PixelColor {
+    /// Playlist entry being shown, `None` for interrupts.
+    key: Option<EntryKey>,
+    message: Message<C>,
+    /// The message text with missing glyphs replaced, split by color.
+    segments: Vec<Segment<C>>,
+    /// Width of the text in pixels.
+    width: i32,
+    /// Where each character starts relative to the start of the text, only filled in for color effects.
+    glyph_offsets: Vec<i32>,
+    scroller: Scroller,
+    since: Instant,
+    passes_at_start: u32,
+    suspended_at: Option<Instant>,
+    /// When the attention effect started, moved on by time spent suspended.
+    attention_since: Instant,
+    /// Whether the attention effect was changing the look in the last frame.
+    alternate: bool,
+}
+
+impl<C> Showing<C> where C: PixelColor {
+    fn new(key: Option<EntryKey>, message: Message<C>, settings: &TaskSettings, view_width: i32, now: Instant) -> Result<Self, u8g2_fonts::LookupError> {
+        let text = settings.font.prepare(&message.text)?.into_owned();
+        // Text without any visible pixels just clears the screen once.
+        let (width, mode) = match settings.font.text_width(&text)? {
+            None => (0, ScrollMode::Static(Alignment::Left)),
+            Some(width) => {
+                let width = width as i32;
+                match settings.static_when_fits {
+                    Some(alignment) if width <= view_width => (width, ScrollMode::Static(alignment)),
+                    _ => (width, settings.scroll_mode)
+                }
+            }
+        };
+        let segments = segments(&settings.font, &text, &message.spans, message.color)?;
+        let mut glyph_offsets = Vec::new();
+        if message.effect.is_some() {
+            let mut prefix = String::new();
+            for c in text.chars() {
+                glyph_offsets.push(settings.font.advance(&prefix)?);
+                prefix.push(c);
+            }
+        }
+        Ok(Showing {
+            key,
+            message,
+            segments,
+            width,
+            glyph_offsets,
+            scroller: Scroller::new(mode, width, view_width),
+            since: now,
+            passes_at_start: 0,
+            suspended_at: None,
+            attention_since: now,
+            alternate: false,
+        })
+    }
+
+    fn status(&self) -> ShowingStatus<C> {
+        ShowingStatus {
+            id: self.key.map(|(id, _)| id),
+            text: self.message.text.clone(),
+            color: self.message.color,
+            black: self.message.black,
+            priority: self.message.priority,
+            x_pos: self.scroller.x_pos(),
+            direction: self.scroller.direction(),
+            passes: self.scroller.passes(),
+        }
+    }
+
+    fn is_interrupt(&self) -> bool {
+        self.key.is_none()
+    }
+
+    /// Whether every frame looks different, because the text moves or its colors change.
+    fn redraws_each_frame(&self) -> bool {
+        !self.scroller.is_static() || self.message.effect.as_ref().is_some_and(|(effect, _)| effect.is_animated())
+    }
+
+    /// Whether the scroller has to be stepped, either to redraw the text or to count passes.
+    fn needs_frames(&self) -> bool {
+        self.redraws_each_frame() || matches!(self.message.dwell, Dwell::Passes(_))
+    }
+
+    fn attention_elapsed(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.attention_since)
+    }
+
+    /// The attention effect changing the look of the message at `now`, if any.
+    fn alternate_look(&self, now: Instant) -> Option<AttentionEffect> {
+        self.message.attention.filter(|attention| attention.is_alternate(self.attention_elapsed(now))).map(|attention| attention.effect)
+    }
+
+    /// Whether the text stays where it is while the attention effect runs.
+    fn holds_still(&self, now: Instant) -> bool {
+        self.message.attention.is_some_and(|attention| attention.holds_text(self.attention_elapsed(now)))
+    }
+
+    /// When the attention effect changes the look of the message next.
+    fn next_attention_change(&self, now: Instant) -> Option<Instant> {
+        let attention = self.message.attention?;
+        attention.next_change(self.attention_elapsed(now)).map(|change| self.attention_since + change)
+    }
+
+    fn dwell_elapsed(&self, now: Instant) -> bool {
+        match self.message.dwell {
+            Dwell::Duration(duration) => now.saturating_duration_since(self.since) >= duration,
+            Dwell::Passes(passes) => self.scroller.passes() - self.passes_at_start >= passes,
+            Dwell::Forever => false
+        }
+    }
+
+    /// When the message has to be looked at again regardless of frames, for its dwell or its expiry.
+    fn deadline(&self) -> Option<Instant> {
+        let dwell_end = match self.message.dwell {
+            Dwell::Duration(duration) => Some(self.since + duration),
+            _ => None
+        };
+        earliest(dwell_end, self.message.expires_at)
+    }
+
+    /// Whether the message is done, either by its dwell or by its expiry.
+    fn finished(&self, now: Instant) -> bool {
+        self.dwell_elapsed(now) || self.message.is_expired(now)
+    }
+
+    fn restart_dwell(&mut self, now: Instant) {
+        self.since = now;
+        self.passes_at_start = self.scroller.passes();
+    }
+
+    fn suspend(&mut self, now: Instant) {
+        self.suspended_at = Some(now);
+    }
+
+    /// Picks up where the message left off, time spent suspended does not count towards the dwell.
+    fn resume(&mut self, now: Instant) {
+        if let Some(suspended_at) = self.suspended_at.take() {
+            let suspended_for = now.saturating_duration_since(suspended_at);
+            self.since += suspended_for;
+            self.attention_since += suspended_for;
+        }
+    }
+}
+
+fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b)
+    }
+}
+
+/// Body of the long-lived thread drawing messages onto the target.
+///
+/// The task sleeps until the next frame or deadline is due, or until the printer sends a [`Command`].
+pub(crate) struct DisplayTask<C, E, Target> where Target: DrawTarget<Color=C, Error=E>, C: PixelColor, E: Error {
+    target: Arc<RwLock<Target>>,
+    settings: TaskSettings,
+    clock: Arc<dyn Clock>,
+    commands: Receiver<Command>,
+    state: Arc<Mutex<TaskState<C, E>>>,
+    playlist: Arc<Mutex<Playlist<C>>>,
+    view_size: Size,
+    showing: Option<Showing<C>>,
+    /// Transition into `showing` while it is under way.
+    transition: Option<RunningTransition<C>>,
+    suspended: Vec<Showing<C>>,
+    paused: bool,
+    timer: PixelTimer,
+    /// The level of the schedule in effect, the screen only shows the background of the message while blank.
+    scheduled: Option<ScheduledLevel>,
+    /// Whether `showing` changed since the status was last published.
+    status_dirty: bool,
+}
+
+impl<C, E, Target> DisplayTask<C, E, Target> where Target: DrawTarget<Color=C, Error=E>, C: PixelColor, E: Error {
+    pub(crate) fn new(target: Arc<RwLock<Target>>, settings: TaskSettings, clock: Arc<dyn Clock>, commands: Receiver<Command>, state: Arc<Mutex<TaskState<C, E>>>, playlist: Arc<Mutex<Playlist<C>>>, paused: bool) -> Self {
+        DisplayTask {
+            target,
+            commands,
+            state,
+            playlist,
+            view_size: Size::zero(),
+            showing: None,
+            transition: None,
+            suspended: Vec::new(),
+            paused,
+            timer: PixelTimer::new(settings.scroll_spp_ms, clock.now()),
+            settings,
+            clock,
+            scheduled: None,
+            status_dirty: true,
+        }
+    }
+
+    /// Runs until the printer stops the task, leaving any error behind in the task state.
+    pub(crate) fn run(mut self) {
+        if let Err(error) = self.run_until_stopped() {
+            if let Ok(mut state) = self.state.lock() {
+                state.error = Some(error);
+            }
+        }
+    }
+
+    fn run_until_stopped(&mut self) -> Result<(), PrinterError<E>> {
+        self.view_size = self.target.read()?.bounding_box().size;
+        let mut sync_reply: Option<Sender<()>> = None;
+        loop {
+            if !self.paused {
+                self.update_message()?;
+                self.apply_schedule()?;
+                self.step_frame()?;
+            }
+            self.publish_status()?;
+            if let Some(reply) = sync_reply.take() {
+                let _ = reply.send(());
+            }
+
+            let wait_time = self.next_wakeup()?.and_then(|wakeup| self.clock.wait_time(wakeup));
+            let command = match wait_time {
+                Some(wait_time) => match self.commands.recv_timeout(wait_time) {
+                    Ok(command) => Some(command),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return Ok(())
+                },
+                None => match self.commands.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => return Ok(())
+                }
+            };
+            match command {
+                Some(Command::Settings(settings)) => {
+                    if let Some(brightness) = self.settings.schedule.as_ref().and_then(Schedule::brightness) {
+                        brightness.set_scheduled(u8::MAX);
+                    }
+                    self.timer.set_ms_per_pixel(settings.scroll_spp_ms);
+                    self.settings = settings;
+                    self.update_schedule();
+                    self.restart_current()?;
+                }
+                Some(Command::Speed(ms_per_pixel)) => {
+                    self.timer.set_ms_per_pixel(ms_per_pixel);
+                    self.settings.scroll_spp_ms = ms_per_pixel;
+                }
+                Some(Command::Redraw) => self.redraw()?,
+                Some(Command::Pause) => self.pause(),
+                Some(Command::Resume) => self.resume(),
+                Some(Command::Stop) => return Ok(()),
+                Some(Command::Sync(reply)) => sync_reply = Some(reply),
+                Some(Command::Refresh) | None => {}
+            }
+        }
+    }
+
+    fn view_width(&self) -> i32 {
+        self.view_size.width as i32
+    }
+
+    /// When the loop has to run again without being told to, `None` to sleep until the next command.
+    fn next_wakeup(&self) -> Result<Option<Instant>, PrinterError<E>> {
+        if self.paused {
+            return Ok(None);
+        }
+        let mut wakeup = self.playlist.lock()?.next_expiry();
+        if let Some(current) = &self.showing {
+            wakeup = earliest(wakeup, current.deadline());
+            wakeup = earliest(wakeup, current.next_attention_change(self.clock.now()));
+            if current.needs_frames() {
+                wakeup = earliest(wakeup, Some(self.timer.next_pixel_at()));
+            }
+        }
+        if let Some(transition) = &self.transition {
+            wakeup = earliest(wakeup, Some(self.timer.next_pixel_at().min(transition.ends_at())));
+        }
+        if let Some(schedule) = &self.settings.schedule {
+            let now = self.clock.now();
+            wakeup = earliest(wakeup, Some(now + schedule.until_next_minute(self.clock.system_time())));
+        }
+        Ok(wakeup)
+    }
+
+    fn pause(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.paused = true;
+        let now = self.clock.now();
+        if let Some(current) = self.showing.as_mut() {
+            current.suspend(now);
+        }
+    }
+
+    fn resume(&mut self) {
+        if !self.paused {
+            return;
+        }
+        self.paused = false;
+        let now = self.clock.now();
+        if let Some(current) = self.showing.as_mut() {
+            current.resume(now);
+        }
+        self.timer.reset(now);
+    }
+
+    fn publish_status(&mut self) -> Result<(), PrinterError<E>> {
+        if self.status_dirty {
+            let mut state = self.state.lock()?;
+            state.showing = self.showing.as_ref().map(Showing::status);
+            state.scheduled = self.scheduled;
+            self.status_dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Moves the text along by however many pixels are due since the last frame.
+    fn step_frame(&mut self) -> Result<(), PrinterError<E>> {
+        let now = self.clock.now();
+        if let Some(transition) = &self.transition {
+            // The text holds still until the transition is over, frames come at the pace of the scrolling.
+            let pixels = self.timer.advance(now);
+            if pixels == 0 && now < transition.ends_at() {
+                return Ok(());
+            }
+            self.status_dirty = true;
+            return self.redraw();
+        }
+        let blank = self.is_blank();
+        let current = match self.showing.as_mut() {
+            Some(current) => current,
+            None => {
+                self.timer.reset(now);
+                return Ok(());
+            }
+        };
+        // Time spent without frames must not turn into a jump once frames are needed again.
+        let pixels = if current.needs_frames() {
+            self.timer.advance(now)
+        } else {
+            self.timer.reset(now);
+            0
+        };
+        let alternate = current.alternate_look(now).is_some();
+        let toggled = alternate != current.alternate;
+        if pixels == 0 && !toggled {
+            return Ok(());
+        }
+        current.alternate = alternate;
+        if !current.holds_still(now) {
+            current.scroller.advance(pixels);
+        }
+        if (toggled || current.redraws_each_frame()) && !blank {
+            Self::draw_frame(self.target.write()?.deref_mut(), &self.settings.font, current, false, now)?;
+        }
+        self.status_dirty = true;
+        Ok(())
+    }
+
+    /// Draws `showing` as it looks at `now`, or just its background if `blank`.
+    fn draw_frame<D>(target: &mut D, font: &Font, showing: &Showing<C>, blank: bool, now: Instant) -> Result<(), PrinterError<D::Error>> where D: DrawTarget<Color=C> {
+        let message = &showing.message;
+        let look = if blank { None } else { showing.alternate_look(now) };
+        let background = match look {
+            Some(AttentionEffect::Flash | AttentionEffect::Invert) => message.color,
+            _ => message.black
+        };
+        target.clear(background).map_err(PrinterError::DrawTarget)?;
+        if blank || matches!(look, Some(AttentionEffect::Blink | AttentionEffect::Flash)) {
+            return Ok(());
+        }
+        let inverted = look == Some(AttentionEffect::Invert);
+        let elapsed = now.saturating_duration_since(showing.since);
+        for x in showing.scroller.draw_positions() {
+            match &message.effect {
+                Some((effect, to_color)) if !inverted => {
+                    let mut recolored = Recolor::new(&mut *target, |point: Point| {
+                        let offset = point.x - x;
+                        let character = showing.glyph_offsets.partition_point(|&start| start <= offset).saturating_sub(1);
+                        to_color(effect.color_at(offset, showing.width, character, elapsed))
+                    });
+                    for segment in &showing.segments {
+                        font.draw(&segment.text, x + segment.x_offset, message.color, &mut recolored)?;
+                    }
+                }
+                _ => {
+                    for segment in &showing.segments {
+                        let color = if inverted { message.black } else { segment.color };
+                        font.draw(&segment.text, x + segment.x_offset, color, target)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A snapshot of the screen for `incoming` to transition from, `None` if it replaces the screen at once.
+    fn outgoing(&self, incoming: &Showing<C>, now: Instant) -> Result<Option<(Frame<C>, C)>, PrinterError<E>> {
+        let current = match &self.showing {
+            Some(current) if incoming.message.transition.is_some() => current,
+            _ => return Ok(None)
+        };
+        let mut frame = Frame::new(self.view_size, current.message.black);
+        Self::draw_frame(&mut frame, &self.settings.font, current, self.is_blank(), now).map_err(PrinterError::into_target_error)?;
+        Ok(Some((frame, current.message.black)))
+    }
+
+    fn show(&mut self, showing: Showing<C>) -> Result<(), PrinterError<E>> {
+        let outgoing = self.outgoing(&showing, self.clock.now())?;
+        self.show_after(showing, outgoing)
+    }
+
+    /// Puts `showing` on screen, transitioning from `outgoing` if given.
+    fn show_after(&mut self, showing: Showing<C>, outgoing: Option<(Frame<C>, C)>) -> Result<(), PrinterError<E>> {
+        let now = self.clock.now();
+        self.transition = match (showing.message.transition, outgoing) {
+            (Some((transition, duration, mix)), Some((from, from_black))) => Some(RunningTransition::new(transition, duration, mix, from, from_black, now)),
+            _ => None
+        };
+        self.showing = Some(showing);
+        // The new message may override the schedule, or stop overriding it.
+        self.update_schedule();
+        self.timer.reset(now);
+        self.status_dirty = true;
+        self.redraw()
+    }
+
+    fn redraw(&mut self) -> Result<(), PrinterError<E>> {
+        let now = self.clock.now();
+        if self.draw_transition(now)? {
+            return Ok(());
+        }
+        if let Some(current) = &self.showing {
+            Self::draw_frame(self.target.write()?.deref_mut(), &self.settings.font, current, self.is_blank(), now)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the transition into the message on screen as far as it got by `now`, returns false once it is over.
+    fn draw_transition(&mut self, now: Instant) -> Result<bool, PrinterError<E>> {
+        let under_way = self.transition.as_ref().is_some_and(|transition| transition.progress(now) < 1.0);
+        if !under_way || self.is_blank() {
+            self.transition = None;
+            return Ok(false);
+        }
+        let (Some(transition), Some(current)) = (&self.transition, &self.showing) else { return Ok(false) };
+        let mut incoming = Frame::new(self.view_size, current.message.black);
+        Self::draw_frame(&mut incoming, &self.settings.font, current, false, now).map_err(PrinterError::into_target_error)?;
+        let colors = transition.compose(&incoming, current.message.black, transition.progress(now));
+        let mut target = self.target.write()?;
+        // Clearing first keeps every frame starting with a clear, as frames without a transition do.
+        target.clear(current.message.black).map_err(PrinterError::DrawTarget)?;
+        let area = target.bounding_box();
+        target.fill_contiguous(&area, colors).map_err(PrinterError::DrawTarget)?;
+        Ok(true)
+    }
+
+    fn is_blank(&self) -> bool {
+        self.scheduled == Some(ScheduledLevel::Blank)
+    }
+
+    /// Works out the level of the schedule for this time and dims the brightness to it, returns whether anything changed.
+    ///
+    /// Messages at or above the override priority of the schedule are shown as if outside every rule.
+    fn update_schedule(&mut self) -> bool {
+        let (level, dimmed) = match &self.settings.schedule {
+            Some(schedule) => {
+                let overridden = self.showing.as_ref().is_some_and(|current| schedule.overrides(current.message.priority));
+                let level = if overridden { None } else { schedule.level_at(self.clock.system_time()) };
+                let scheduled_brightness = match level {
+                    Some(ScheduledLevel::Brightness(brightness)) => brightness,
+                    _ => u8::MAX
+                };
+                (level, schedule.brightness().is_some_and(|brightness| brightness.set_scheduled(scheduled_brightness)))
+            }
+            None => (None, false)
+        };
+        if level == self.scheduled {
+            return dimmed;
+        }
+        self.scheduled = level;
+        self.status_dirty = true;
+        true
+    }
+
+    /// Redraws the screen when the schedule dims or blanks it, or lets it run normally again.
+    fn apply_schedule(&mut self) -> Result<(), PrinterError<E>> {
+        if self.update_schedule() {
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    /// Clears the screen once nothing is left to show.
+    fn show_nothing(&mut self) -> Result<(), PrinterError<E>> {
+        self.transition = None;
+        if let Some(previous) = self.showing.take() {
+            self.target.write()?.clear(previous.message.black).map_err(PrinterError::DrawTarget)?;
+            self.status_dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Lays out the message on screen and those suspended under it again, e.g. after the font changed.
+    fn restart_current(&mut self) -> Result<(), PrinterError<E>> {
+        let now = self.clock.now();
+        for suspended in std::mem::take(&mut self.suspended) {
+            let mut restarted = Showing::new(suspended.key, suspended.message, &self.settings, self.view_width(), now)?;
+            restarted.suspend(now);
+            self.suspended.push(restarted);
+        }
+        if let Some(current) = self.showing.take() {
+            let restarted = Showing::new(current.key, current.message, &self.settings, self.view_width(), now)?;
+            self.show(restarted)?;
+        }
+        Ok(())
+    }
+
+    /// Decides what should be on screen and draws it if it changed.
+    fn update_message(&mut self) -> Result<(), PrinterError<E>> {
+        let now = self.clock.now();
+        let elapsed = self.showing.as_ref().is_some_and(|current| current.finished(now));
+        let above = match &self.showing {
+            Some(current) if !elapsed => Some(current.message.priority),
+            _ => None
+        };
+        let interrupt = {
+            let mut playlist = self.playlist.lock()?;
+            playlist.remove_expired(now);
+            playlist.take_interrupt(above)
+        };
+        if let Some(interrupt) = interrupt {
+            let interrupt = Showing::new(None, interrupt, &self.settings, self.view_width(), now)?;
+            let outgoing = self.outgoing(&interrupt, now)?;
+            if let Some(mut current) = self.showing.take() {
+                if !elapsed {
+                    current.suspend(now);
+                    self.suspended.push(current);
+                } else if let Some(key) = current.key {
+                    let mut playlist = self.playlist.lock()?;
+                    if playlist.current().map(|(current_key, _)| current_key) == Some(key) {
+                        playlist.advance();
+                    }
+                }
+            }
+            return self.show_after(interrupt, outgoing);
+        }
+
+        if elapsed && self.showing.as_ref().is_some_and(Showing::is_interrupt) {
+            // Playlist entries removed or replaced while the interrupt was on screen are not resumed.
+            let current_key = self.playlist.lock()?.current().map(|(key, _)| key);
+            while let Some(mut resumed) = self.suspended.pop() {
+                let stale = resumed.key.is_some_and(|key| Some(key) != current_key);
+                if !stale && !resumed.message.is_expired(now) {
+                    resumed.resume(now);
+                    return self.show(resumed);
+                }
+            }
+            self.show_nothing()?;
+        }
+        if self.showing.as_ref().is_some_and(Showing::is_interrupt) {
+            return Ok(());
+        }
+        self.update_from_playlist(now)
+    }
+
+    fn update_from_playlist(&mut self, now: Instant) -> Result<(), PrinterError<E>> {
+        let mut playlist = self.playlist.lock()?;
+        if let Some(current) = self.showing.as_mut() {
+            if current.dwell_elapsed(now) {
+                if playlist.current().map(|(key, _)| key) == current.key {
+                    playlist.advance();
+                }
+                current.restart_dwell(now);
+            }
+        }
+        let shown_key = self.showing.as_ref().and_then(|current| current.key);
+        let next = match playlist.current() {
+            Some((key, _)) if shown_key == Some(key) => None,
+            Some((key, message)) => Some(Some((key, message.clone()))),
+            None => self.showing.is_some().then_some(None)
+        };
+        drop(playlist);
+
+        match next {
+            Some(Some((key, message))) => {
+                let next_showing = Showing::new(Some(key), message, &self.settings, self.view_width(), now)?;
+                self.show(next_showing)
+            }
+            Some(None) => self.show_nothing(),
+            None => Ok(())
+        }
+    }
+}
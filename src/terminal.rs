@@ -0,0 +1,120 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{OriginDimensions, Point, Size};
+use embedded_graphics::pixelcolor::{PixelColor, Rgb888, RgbColor};
+use embedded_graphics::Pixel;
+use crate::record::Frame;
+
+impl<C> Frame<C> where C: PixelColor + Into<Rgb888> {
+    /// Renders the frame with ANSI 24-bit colors, two rows of pixels per line of `▀` half blocks.
+    pub fn to_ansi(&self) -> String {
+        let size = self.size();
+        let mut ansi = String::new();
+        for y in (0..size.height as i32).step_by(2) {
+            for x in 0..size.width as i32 {
+                if let Some(top) = self.pixel(Point::new(x, y)) {
+                    let top: Rgb888 = top.into();
+                    let _ = write!(ansi, "\x1b[38;2;{};{};{}m", top.r(), top.g(), top.b());
+                }
+                match self.pixel(Point::new(x, y + 1)) {
+                    Some(bottom) => {
+                        let bottom: Rgb888 = bottom.into();
+                        let _ = write!(ansi, "\x1b[48;2;{};{};{}m", bottom.r(), bottom.g(), bottom.b());
+                    }
+                    // Odd heights leave the lower half of the last line to the terminal background.
+                    None => ansi.push_str("\x1b[49m")
+                }
+                ansi.push('▀');
+            }
+            ansi.push_str("\x1b[0m\n");
+        }
+        ansi
+    }
+}
+
+/// Draw target that previews the matrix on a terminal, see [`Frame::to_ansi`].
+///
+/// Drawing only updates the image in memory, [`TerminalTarget::flush`] writes it out, replacing the previous one.
+#[derive(Debug)]
+pub struct TerminalTarget<C, W> where C: PixelColor, W: Write {
+    frame: Frame<C>,
+    out: W,
+    /// Terminal lines written by the last flush, to move back over.
+    lines_drawn: u32,
+}
+
+impl<C, W> TerminalTarget<C, W> where C: PixelColor + Into<Rgb888>, W: Write {
+    pub fn new(size: Size, background: C, out: W) -> Self {
+        TerminalTarget {
+            frame: Frame::new(size, background),
+            out,
+            lines_drawn: 0,
+        }
+    }
+
+    /// The image as last drawn.
+    pub fn frame(&self) -> &Frame<C> {
+        &self.frame
+    }
+
+    /// Writes the image to the terminal, over the one written by the previous flush.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.lines_drawn > 0 {
+            write!(self.out, "\x1b[{}A\r", self.lines_drawn)?;
+        }
+        self.out.write_all(self.frame.to_ansi().as_bytes())?;
+        self.lines_drawn = self.frame.size().height.div_ceil(2);
+        self.out.flush()
+    }
+
+    /// Lets the next flush start on a fresh line instead of overwriting, e.g. after logging something.
+    pub fn detach(&mut self) {
+        self.lines_drawn = 0;
+    }
+}
+
+impl<C, W> OriginDimensions for TerminalTarget<C, W> where C: PixelColor, W: Write {
+    fn size(&self) -> Size {
+        self.frame.size()
+    }
+}
+
+impl<C, W> DrawTarget for TerminalTarget<C, W> where C: PixelColor, W: Write {
+    type Color = C;
+    type Error = io::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error> where I: IntoIterator<Item=Pixel<Self::Color>> {
+        self.frame.draw_iter(pixels).map_err(|never| match never {})
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.frame.clear(color).map_err(|never| match never {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_rows_into_half_blocks() {
+        let mut frame = Frame::new(Size::new(2, 3), Rgb888::BLACK);
+        frame.draw_iter([Pixel(Point::new(0, 0), Rgb888::RED), Pixel(Point::new(1, 2), Rgb888::BLUE)]).unwrap();
+        let ansi = frame.to_ansi();
+        let lines: Vec<&str> = ansi.lines().collect();
+        assert_eq!(lines, [
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;0m▀\x1b[38;2;0;0;0m\x1b[48;2;0;0;0m▀\x1b[0m",
+            "\x1b[38;2;0;0;0m\x1b[49m▀\x1b[38;2;0;0;255m\x1b[49m▀\x1b[0m",
+        ]);
+    }
+
+    #[test]
+    fn flush_redraws_in_place() {
+        let mut target = TerminalTarget::new(Size::new(1, 2), Rgb888::BLACK, Vec::new());
+        target.flush().unwrap();
+        target.flush().unwrap();
+        let written = String::from_utf8(target.out.clone()).unwrap();
+        assert_eq!(written.matches("\x1b[1A\r").count(), 1);
+    }
+}
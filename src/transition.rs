@@ -0,0 +1,127 @@
+use std::time::{Duration, Instant};
+use embedded_graphics::geometry::{OriginDimensions, Point};
+use embedded_graphics::pixelcolor::{PixelColor, Rgb888, RgbColor};
+use crate::record::Frame;
+use crate::scroll::ScrollDirection;
+
+/// How a message replaces the one before it on screen, see [`crate::Message::with_transition`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Transition {
+    /// An edge moves across the panel in the given direction, uncovering the new message behind it.
+    Wipe(ScrollDirection),
+    /// The new message slides in, pushing the old one off the panel in the given direction.
+    Push(ScrollDirection),
+    /// The old message fades out to its background, then the new one fades in.
+    Fade,
+    /// The pixels of the new message replace those of the old one in a scattered order.
+    Dissolve,
+}
+
+/// Blends two colors of the target, see [`mix`].
+pub(crate) type Mix<C> = fn(C, C, f32) -> C;
+
+/// Blends `from` into `to`, `t` going from 0 to 1.
+pub(crate) fn mix<C>(from: C, to: C, t: f32) -> C where C: Into<Rgb888> + From<Rgb888> {
+    let (from, to): (Rgb888, Rgb888) = (from.into(), to.into());
+    let t = t.clamp(0.0, 1.0);
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    C::from(Rgb888::new(channel(from.r(), to.r()), channel(from.g(), to.g()), channel(from.b(), to.b())))
+}
+
+/// Where in `0..1` the pixel at `index` switches over in a dissolve, scattered but the same every time.
+fn dissolve_threshold(index: usize) -> f32 {
+    let hash = (index as u32 ^ 0x9e37_79b9).wrapping_mul(0x85eb_ca6b);
+    let hash = (hash ^ (hash >> 13)).wrapping_mul(0xc2b2_ae35);
+    (hash >> 8) as f32 / (1 << 24) as f32
+}
+
+/// A transition under way, from a snapshot of what was on screen to the message now showing.
+pub(crate) struct RunningTransition<C> where C: PixelColor {
+    transition: Transition,
+    duration: Duration,
+    mix: Mix<C>,
+    from: Frame<C>,
+    from_black: C,
+    since: Instant,
+}
+
+impl<C> RunningTransition<C> where C: PixelColor {
+    pub(crate) fn new(transition: Transition, duration: Duration, mix: Mix<C>, from: Frame<C>, from_black: C, since: Instant) -> Self {
+        RunningTransition { transition, duration, mix, from, from_black, since }
+    }
+
+    pub(crate) fn ends_at(&self) -> Instant {
+        self.since + self.duration
+    }
+
+    /// How far along the transition is at `now`, 1 once it is over.
+    pub(crate) fn progress(&self, now: Instant) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (now.saturating_duration_since(self.since).as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    /// The pixels of the screen, row by row, `progress` of the way from the old frame to `to`.
+    pub(crate) fn compose(&self, to: &Frame<C>, to_black: C, progress: f32) -> Vec<C> {
+        let size = to.size();
+        let width = size.width as i32;
+        let edge = (progress * width as f32).round() as i32;
+        let mut colors = Vec::with_capacity((size.width * size.height) as usize);
+        for y in 0..size.height as i32 {
+            for x in 0..width {
+                let old = |x: i32| self.from.pixel(Point::new(x, y)).unwrap_or(self.from_black);
+                let new = |x: i32| to.pixel(Point::new(x, y)).unwrap_or(to_black);
+                let color = match self.transition {
+                    Transition::Wipe(ScrollDirection::Left) => if x >= width - edge { new(x) } else { old(x) },
+                    Transition::Wipe(ScrollDirection::Right) => if x < edge { new(x) } else { old(x) },
+                    Transition::Push(ScrollDirection::Left) => if x + edge < width { old(x + edge) } else { new(x + edge - width) },
+                    Transition::Push(ScrollDirection::Right) => if x - edge >= 0 { old(x - edge) } else { new(x - edge + width) },
+                    Transition::Fade if progress < 0.5 => (self.mix)(old(x), self.from_black, progress * 2.0),
+                    Transition::Fade => (self.mix)(to_black, new(x), progress * 2.0 - 1.0),
+                    Transition::Dissolve => {
+                        let index = (y * width + x) as usize;
+                        if dissolve_threshold(index) < progress { new(x) } else { old(x) }
+                    }
+                };
+                colors.push(color);
+            }
+        }
+        colors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::draw_target::DrawTarget;
+    use embedded_graphics::geometry::Size;
+    use embedded_graphics::Pixel;
+    use super::*;
+
+    /// A transition from a 4x1 frame lit only on its left pixel, to a blank one.
+    fn from_left_dot(transition: Transition) -> RunningTransition<Rgb888> {
+        let mut from = Frame::new(Size::new(4, 1), Rgb888::BLACK);
+        from.draw_iter([Pixel(Point::zero(), Rgb888::WHITE)]).unwrap();
+        RunningTransition::new(transition, Duration::from_secs(1), mix::<Rgb888>, from, Rgb888::BLACK, Instant::now())
+    }
+
+    #[test]
+    fn transitions_move_between_frames() {
+        let blank = Frame::new(Size::new(4, 1), Rgb888::BLUE);
+        let (w, k, b) = (Rgb888::WHITE, Rgb888::BLACK, Rgb888::BLUE);
+        assert_eq!(from_left_dot(Transition::Wipe(ScrollDirection::Left)).compose(&blank, b, 0.5), [w, k, b, b]);
+        assert_eq!(from_left_dot(Transition::Wipe(ScrollDirection::Right)).compose(&blank, b, 0.5), [b, b, k, k]);
+        assert_eq!(from_left_dot(Transition::Push(ScrollDirection::Right)).compose(&blank, b, 0.25), [b, w, k, k]);
+        assert_eq!(from_left_dot(Transition::Push(ScrollDirection::Left)).compose(&blank, b, 0.25), [k, k, k, b]);
+        assert_eq!(from_left_dot(Transition::Fade).compose(&blank, b, 0.25)[0], Rgb888::new(128, 128, 128));
+        assert_eq!(from_left_dot(Transition::Fade).compose(&blank, k, 0.75), [Rgb888::new(0, 0, 128); 4]);
+        assert_eq!(from_left_dot(Transition::Dissolve).compose(&blank, b, 0.0), [w, k, k, k]);
+        assert_eq!(from_left_dot(Transition::Dissolve).compose(&blank, b, 1.0), [b; 4]);
+    }
+
+    #[test]
+    fn zero_duration_is_over_at_once() {
+        let transition = RunningTransition::new(Transition::Fade, Duration::ZERO, mix::<Rgb888>, Frame::new(Size::new(1, 1), Rgb888::BLACK), Rgb888::BLACK, Instant::now());
+        assert_eq!(transition.progress(transition.ends_at()), 1.0);
+    }
+}